@@ -0,0 +1,645 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::source::{Attribution, LookupOptions, PageMetadata, SourceError, Summary, SummarySource};
+
+#[cfg(target_arch = "wasm32")]
+const STORAGE_KEY: &str = "study-guide-filler-cache";
+
+/// A cached answer from the source.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Cached<T>
+{
+    /// Seconds since the Unix epoch.
+    pub fetched_at: u64,
+    #[serde(alias = "summary", alias = "titles")]
+    pub value: T,
+}
+
+/// Which of the articles checked against a page it links to.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Links
+{
+    pub checked: Vec<String>,
+    pub linked: Vec<String>,
+}
+
+/// Every kind of answer in its own map, each keyed by [`key`].
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct CacheData
+{
+    summaries: HashMap<String, Cached<Summary>>,
+    searches: HashMap<String, Cached<Vec<String>>>,
+    snippets: HashMap<String, Cached<Vec<(String, String)>>>,
+    meanings: HashMap<String, Cached<Vec<String>>>,
+    suggestions: HashMap<String, Cached<Vec<String>>>,
+    metadata: HashMap<String, Cached<PageMetadata>>,
+    links: HashMap<String, Cached<Links>>,
+}
+
+/// The maps of [`CacheData`], to evict from them in one go.
+#[derive(Clone, Copy)]
+enum Table
+{
+    Summaries,
+    Searches,
+    Snippets,
+    Meanings,
+    Suggestions,
+    Metadata,
+    Links,
+}
+
+impl CacheData
+{
+    /// Every entry as `(fetched_at, table, key, size)`, with its size as written.
+    fn entries(&self) -> Vec<(u64, Table, String, usize)>
+    {
+        fn sizes<T: Serialize>(table: Table, map: &HashMap<String, Cached<T>>, entries: &mut Vec<(u64, Table, String, usize)>)
+        {
+            for (key, cached) in map
+            {
+                let size = key.len() + serde_json::to_string(cached).map(|json| json.len()).unwrap_or_default();
+                entries.push((cached.fetched_at, table, key.clone(), size));
+            }
+        }
+        let mut entries = Vec::new();
+        sizes(Table::Summaries, &self.summaries, &mut entries);
+        sizes(Table::Searches, &self.searches, &mut entries);
+        sizes(Table::Snippets, &self.snippets, &mut entries);
+        sizes(Table::Meanings, &self.meanings, &mut entries);
+        sizes(Table::Suggestions, &self.suggestions, &mut entries);
+        sizes(Table::Metadata, &self.metadata, &mut entries);
+        sizes(Table::Links, &self.links, &mut entries);
+        entries
+    }
+
+    fn remove(&mut self, table: Table, key: &str)
+    {
+        match table
+        {
+            Table::Summaries => { self.summaries.remove(key); },
+            Table::Searches => { self.searches.remove(key); },
+            Table::Snippets => { self.snippets.remove(key); },
+            Table::Meanings => { self.meanings.remove(key); },
+            Table::Suggestions => { self.suggestions.remove(key); },
+            Table::Metadata => { self.metadata.remove(key); },
+            Table::Links => { self.links.remove(key); },
+        }
+    }
+
+    /// Drops the entries fetched longest ago until the rest fit in `max_size` bytes.
+    fn shrink(&mut self, max_size: usize)
+    {
+        let mut entries = self.entries();
+        let mut size = entries.iter().map(|(_, _, _, size)| size).sum::<usize>();
+        entries.sort_by_key(|(fetched_at, _, _, _)| *fetched_at);
+        for (_, table, key, entry_size) in entries
+        {
+            if size <= max_size
+            {
+                break;
+            }
+            self.remove(table, &key);
+            size -= entry_size;
+        }
+    }
+
+    fn retain_fresh(&mut self, expiry: u64, now: u64)
+    {
+        fn retain<T>(map: &mut HashMap<String, Cached<T>>, expiry: u64, now: u64)
+        {
+            map.retain(|_, cached| now.saturating_sub(cached.fetched_at) <= expiry);
+        }
+        retain(&mut self.summaries, expiry, now);
+        retain(&mut self.searches, expiry, now);
+        retain(&mut self.snippets, expiry, now);
+        retain(&mut self.meanings, expiry, now);
+        retain(&mut self.suggestions, expiry, now);
+        retain(&mut self.metadata, expiry, now);
+        retain(&mut self.links, expiry, now);
+    }
+}
+
+/// Summaries, search results and page details keyed by source, language and title,
+/// stored on disk natively and in local storage on the web.
+pub struct SummaryCache
+{
+    data: CacheData,
+    #[cfg(not(target_arch = "wasm32"))]
+    path: Option<std::path::PathBuf>,
+    pub expiry: Duration,
+    /// Most bytes written, beyond which the entries fetched longest ago are dropped on save.
+    /// Browsers give local storage about 5 MB.
+    pub max_size: usize,
+    dirty: bool,
+}
+
+impl Default for SummaryCache
+{
+    fn default() -> SummaryCache
+    {
+        SummaryCache {
+            data: CacheData::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            path: None,
+            expiry: Duration::from_secs(30 * 24 * 60 * 60),
+            max_size: 4 * 1024 * 1024,
+            dirty: false,
+        }
+    }
+}
+
+impl SummaryCache
+{
+    /// A cache that is never written anywhere.
+    pub fn in_memory() -> SummaryCache
+    {
+        SummaryCache::default()
+    }
+
+    /// Opens the cache in the user's cache directory.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open_default() -> SummaryCache
+    {
+        match dirs::cache_dir()
+        {
+            Some(dir) => SummaryCache::open(dir.join("study-guide-filler").join("summaries.json")),
+            None => SummaryCache::in_memory(),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open(path: std::path::PathBuf) -> SummaryCache
+    {
+        let data = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        SummaryCache {
+            data,
+            path: Some(path),
+            ..SummaryCache::default()
+        }
+    }
+
+    /// Opens the cache kept in the browser's local storage.
+    #[cfg(target_arch = "wasm32")]
+    pub fn open_default() -> SummaryCache
+    {
+        let data = local_storage()
+            .and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        SummaryCache {
+            data,
+            ..SummaryCache::default()
+        }
+    }
+
+    /// Writes the cache back if anything changed since it was opened or last saved, first
+    /// shrinking it to `max_size`.
+    pub fn save(&mut self) -> io::Result<()>
+    {
+        if !self.dirty
+        {
+            return Ok(());
+        }
+        let mut content = serde_json::to_string(&self.data)?;
+        if content.len() > self.max_size
+        {
+            self.data.shrink(self.max_size);
+            content = serde_json::to_string(&self.data)?;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = &self.path
+        {
+            if let Some(dir) = path.parent()
+            {
+                std::fs::create_dir_all(dir)?;
+            }
+            crate::writer::write_file(path, content.as_bytes())?;
+        }
+        #[cfg(target_arch = "wasm32")]
+        local_storage()
+            .and_then(|storage| storage.set_item(STORAGE_KEY, &content).ok())
+            .ok_or_else(|| io::Error::other("local storage is unavailable"))?;
+
+        self.dirty = false;
+        Ok(())
+    }
+
+    pub fn summary(&self, key: &str) -> Option<&Cached<Summary>>
+    {
+        self.data.summaries.get(key)
+    }
+
+    pub fn insert_summary(&mut self, key: String, summary: Summary)
+    {
+        self.data.summaries.insert(key, Cached { fetched_at: now(), value: summary });
+        self.dirty = true;
+    }
+
+    pub fn is_stale(&self, fetched_at: u64) -> bool
+    {
+        now().saturating_sub(fetched_at) > self.expiry.as_secs()
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.data.summaries.len()
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.data.summaries.is_empty()
+    }
+
+    pub fn stale_count(&self) -> usize
+    {
+        self.data.summaries.values().filter(|cached| self.is_stale(cached.fetched_at)).count()
+    }
+
+    /// Keys of the summaries older than the expiry.
+    pub fn stale_keys(&self) -> Vec<String>
+    {
+        self.data.summaries.iter()
+            .filter(|(_, cached)| self.is_stale(cached.fetched_at))
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    /// Removes every entry older than the expiry, returning how many summaries went.
+    pub fn purge_stale(&mut self) -> usize
+    {
+        let before = self.data.summaries.len();
+        self.data.retain_fresh(self.expiry.as_secs(), now());
+        self.dirty = true;
+        before - self.data.summaries.len()
+    }
+
+    pub fn clear(&mut self)
+    {
+        self.data = CacheData::default();
+        self.dirty = true;
+    }
+}
+
+pub fn key(source: &str, language: &str, title: &str) -> String
+{
+    format!("{}:{}:{}", source, language, title)
+}
+
+/// Wraps a source so everything it answers is served from a [`SummaryCache`] while fresh.
+///
+/// Stale entries are refetched, but still served if the source fails. In offline mode only
+/// the cache is used.
+pub struct CachedSource<S>
+{
+    inner: S,
+    cache: Mutex<SummaryCache>,
+    offline: AtomicBool,
+}
+
+impl<S: SummarySource> CachedSource<S>
+{
+    pub fn new(inner: S, cache: SummaryCache) -> CachedSource<S>
+    {
+        CachedSource {
+            inner,
+            cache: Mutex::new(cache),
+            offline: AtomicBool::new(false),
+        }
+    }
+
+    pub fn inner(&self) -> &S
+    {
+        &self.inner
+    }
+
+    pub fn cache(&self) -> MutexGuard<'_, SummaryCache>
+    {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn is_offline(&self) -> bool
+    {
+        self.offline.load(Ordering::Relaxed)
+    }
+
+    pub fn set_offline(&self, offline: bool)
+    {
+        self.offline.store(offline, Ordering::Relaxed);
+    }
+
+    /// Refetches every stale summary, returning how many were updated.
+    pub async fn refresh_stale(&self) -> usize
+    {
+        let keys = self.cache().stale_keys();
+        let mut refreshed = 0;
+        for key in keys
+        {
+            let mut parts = key.splitn(3, ':');
+            let (Some(source), Some(language), Some(title)) = (parts.next(), parts.next(), parts.next()) else { continue };
+            if source != self.inner.name()
+            {
+                continue;
+            }
+            let options = LookupOptions { language: Some(language.to_string()), ..LookupOptions::default() };
+            if let Ok(summary) = self.inner.summary(title, &options).await
+            {
+                self.cache().insert_summary(key.clone(), summary);
+                refreshed += 1;
+            }
+        }
+        refreshed
+    }
+
+    fn key(&self, title: &str, options: &LookupOptions) -> String
+    {
+        key(self.inner.name(), options.language.as_deref().unwrap_or(self.inner.language()), title)
+    }
+
+    /// Serves an answer kept in `table` from the cache while fresh and otherwise from `fetch`,
+    /// which only runs when needed.
+    async fn cached<T, F>(&self, table: F, key: String, name: &str, fetch: impl Future<Output = Result<T, SourceError>>) -> Result<T, SourceError>
+    where
+        T: Clone,
+        F: Fn(&mut CacheData) -> &mut HashMap<String, Cached<T>>,
+    {
+        let cached = {
+            let mut cache = self.cache();
+            let cached = table(&mut cache.data).get(&key).cloned();
+            cached.map(|cached| (cache.is_stale(cached.fetched_at), cached.value))
+        };
+        match cached
+        {
+            Some((false, value)) => return Ok(value),
+            Some((true, value)) if self.is_offline() => return Ok(value),
+            None if self.is_offline() => return Err(SourceError::NotCached(name.to_string())),
+            _ => (),
+        }
+
+        match fetch.await
+        {
+            Ok(value) => {
+                let mut cache = self.cache();
+                table(&mut cache.data).insert(key, Cached { fetched_at: now(), value: value.clone() });
+                cache.dirty = true;
+                Ok(value)
+            },
+            Err(e) => cached.map(|(_, value)| value).ok_or(e),
+        }
+    }
+}
+
+impl<S: SummarySource> SummarySource for CachedSource<S>
+{
+    fn name(&self) -> &str
+    {
+        self.inner.name()
+    }
+
+    fn language(&self) -> &str
+    {
+        self.inner.language()
+    }
+
+    fn attribution(&self) -> Attribution
+    {
+        self.inner.attribution()
+    }
+
+    async fn search(&self, query: &str, options: &LookupOptions) -> Result<Vec<String>, SourceError>
+    {
+        let key = self.key(&query.to_lowercase(), options);
+        self.cached(|data| &mut data.searches, key, query, self.inner.search(query, options)).await
+    }
+
+    async fn snippets(&self, query: &str, options: &LookupOptions) -> Result<Vec<(String, String)>, SourceError>
+    {
+        let key = self.key(&query.to_lowercase(), options);
+        self.cached(|data| &mut data.snippets, key, query, self.inner.snippets(query, options)).await
+    }
+
+    async fn summary(&self, title: &str, options: &LookupOptions) -> Result<Summary, SourceError>
+    {
+        self.cached(|data| &mut data.summaries, self.key(title, options), title, self.inner.summary(title, options)).await
+    }
+
+    async fn meanings(&self, title: &str, options: &LookupOptions) -> Result<Vec<String>, SourceError>
+    {
+        self.cached(|data| &mut data.meanings, self.key(title, options), title, self.inner.meanings(title, options)).await
+    }
+
+    async fn suggestions(&self, query: &str, options: &LookupOptions) -> Result<Vec<String>, SourceError>
+    {
+        let key = self.key(&query.to_lowercase(), options);
+        self.cached(|data| &mut data.suggestions, key, query, self.inner.suggestions(query, options)).await
+    }
+
+    async fn metadata(&self, title: &str, options: &LookupOptions) -> Result<PageMetadata, SourceError>
+    {
+        self.cached(|data| &mut data.metadata, self.key(title, options), title, self.inner.metadata(title, options)).await
+    }
+
+    /// Only asks the source about the targets not checked against `title` yet.
+    async fn linked(&self, title: &str, targets: &[String], options: &LookupOptions) -> Result<Vec<String>, SourceError>
+    {
+        let key = self.key(title, options);
+        let answer = |links: &Links| targets.iter().filter(|target| links.linked.contains(target)).cloned().collect::<Vec<_>>();
+        let cached = {
+            let cache = self.cache();
+            cache.data.links.get(&key).map(|cached| (cache.is_stale(cached.fetched_at), cached.value.clone()))
+        };
+        let covers = |links: &Links| targets.iter().all(|target| links.checked.contains(target));
+        let mut links = match &cached
+        {
+            Some((stale, links)) if covers(links) && (!stale || self.is_offline()) => return Ok(answer(links)),
+            _ if self.is_offline() => return Err(SourceError::NotCached(title.to_string())),
+            Some((false, links)) => links.clone(),
+            _ => Links::default(),
+        };
+
+        let unchecked = targets.iter().filter(|target| !links.checked.contains(target)).cloned().collect::<Vec<_>>();
+        match self.inner.linked(title, &unchecked, options).await
+        {
+            Ok(linked) => {
+                links.checked.extend(unchecked);
+                links.linked.extend(linked);
+                let mut cache = self.cache();
+                cache.data.links.insert(key, Cached { fetched_at: now(), value: links.clone() });
+                cache.dirty = true;
+                Ok(answer(&links))
+            },
+            Err(e) => cached.filter(|(_, links)| covers(links)).map(|(_, links)| answer(&links)).ok_or(e),
+        }
+    }
+}
+
+/// Seconds since the Unix epoch.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now() -> u64
+{
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn now() -> u64
+{
+    (js_sys::Date::now() / 1000.0) as u64
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage>
+{
+    web_sys::window()?.local_storage().ok().flatten()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::source::fixture::FixtureSource;
+
+    fn summary(title: &str, text: &str) -> Summary
+    {
+        Summary {
+            title: title.to_string(),
+            text: text.to_string(),
+            ..Summary::default()
+        }
+    }
+
+    /// A source that knows `pages`, over a cache holding `cached` as `(title, text, fetched_at)`.
+    fn source(pages: &[(&str, &str)], cached: &[(&str, &str, u64)]) -> CachedSource<FixtureSource>
+    {
+        let mut fixture = FixtureSource::new();
+        for (title, text) in pages
+        {
+            fixture = fixture.with_page(*title, *text);
+        }
+        let mut cache = SummaryCache::in_memory();
+        for (title, text, fetched_at) in cached
+        {
+            cache.data.summaries.insert(key("fixture", "en", title), Cached {
+                fetched_at: *fetched_at,
+                value: summary(title, text),
+            });
+        }
+        CachedSource::new(fixture, cache)
+    }
+
+    fn fetch(source: &CachedSource<FixtureSource>, title: &str) -> Result<String, SourceError>
+    {
+        pollster::block_on(source.summary(title, &LookupOptions::default())).map(|summary| summary.text)
+    }
+
+    #[test]
+    fn serves_fresh_entries_and_refetches_stale_ones()
+    {
+        let source = source(
+            &[("Mercury", "The planet."), ("Venus", "The other planet.")],
+            &[("Mercury", "Cached.", now()), ("Venus", "Old.", 0)],
+        );
+        assert_eq!(fetch(&source, "Mercury"), Ok("Cached.".to_string()));
+        assert_eq!(fetch(&source, "Venus"), Ok("The other planet.".to_string()));
+        let cache = source.cache();
+        assert!(!cache.is_stale(cache.summary("fixture:en:Venus").unwrap().fetched_at));
+    }
+
+    #[test]
+    fn offline_uses_only_the_cache()
+    {
+        let source = source(&[("Mercury", "The planet."), ("Venus", "The other planet.")], &[("Venus", "Old.", 0)]);
+        source.set_offline(true);
+        assert_eq!(fetch(&source, "Mercury"), Err(SourceError::NotCached("Mercury".to_string())));
+        assert_eq!(fetch(&source, "Venus"), Ok("Old.".to_string()));
+    }
+
+    #[test]
+    fn serves_a_stale_entry_when_the_source_fails()
+    {
+        let source = source(&[], &[("Mercury", "Old.", 0)]);
+        assert_eq!(fetch(&source, "Mercury"), Ok("Old.".to_string()));
+        assert_eq!(fetch(&source, "Venus"), Err(SourceError::MissingPage("Venus".to_string())));
+    }
+
+    #[test]
+    fn purges_only_stale_entries()
+    {
+        let source = source(&[], &[("Mercury", "Fresh.", now()), ("Venus", "Old.", 0)]);
+        let mut cache = source.cache();
+        assert_eq!(cache.stale_count(), 1);
+        assert_eq!(cache.purge_stale(), 1);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.summary("fixture:en:Mercury").is_some());
+    }
+
+    #[test]
+    fn refreshes_stale_entries_from_the_source()
+    {
+        let source = source(&[("Venus", "The other planet.")], &[("Mercury", "Fresh.", now()), ("Venus", "Old.", 0)]);
+        assert_eq!(pollster::block_on(source.refresh_stale()), 1);
+        let cache = source.cache();
+        assert_eq!(cache.stale_count(), 0);
+        assert_eq!(cache.summary("fixture:en:Venus").unwrap().value.text, "The other planet.");
+        assert_eq!(cache.summary("fixture:en:Mercury").unwrap().value.text, "Fresh.");
+    }
+
+    #[test]
+    fn serves_metadata_links_and_snippets_offline_once_fetched()
+    {
+        let source = source(&[("Mercury", "The planet nearest the Sun."), ("Sun", "The star.")], &[]);
+        let options = LookupOptions::default();
+        let targets = ["Sun".to_string(), "Venus".to_string()];
+        let metadata = pollster::block_on(source.metadata("Mercury", &options)).unwrap();
+        let linked = pollster::block_on(source.linked("Mercury", &targets, &options)).unwrap();
+        let snippets = pollster::block_on(source.snippets("planet", &options)).unwrap();
+
+        source.set_offline(true);
+        assert_eq!(pollster::block_on(source.metadata("Mercury", &options)).unwrap().length, metadata.length);
+        assert_eq!(pollster::block_on(source.linked("Mercury", &targets, &options)), Ok(linked));
+        assert_eq!(pollster::block_on(source.linked("Mercury", &targets[1..], &options)), Ok(Vec::new()));
+        assert_eq!(pollster::block_on(source.snippets("Planet", &options)), Ok(snippets));
+        assert_eq!(
+            pollster::block_on(source.linked("Mercury", &["Mars".to_string()], &options)),
+            Err(SourceError::NotCached("Mercury".to_string())),
+        );
+    }
+
+    #[test]
+    fn only_asks_about_links_not_checked_yet()
+    {
+        let source = source(&[("Mercury", "The planet nearest the Sun, inside Venus.")], &[]);
+        let options = LookupOptions::default();
+        pollster::block_on(source.linked("Mercury", &["Sun".to_string()], &options)).unwrap();
+        pollster::block_on(source.linked("Mercury", &["Venus".to_string()], &options)).unwrap();
+        let links = source.cache().data.links["fixture:en:Mercury"].value.clone();
+        assert_eq!(links.checked, ["Sun", "Venus"]);
+        assert_eq!(links.linked, ["Sun", "Venus"]);
+    }
+
+    #[test]
+    fn saving_drops_the_oldest_entries_beyond_the_size_cap()
+    {
+        let source = source(&[], &[("Mercury", "Oldest.", 1), ("Venus", "Older.", 2), ("Earth", "Newest.", 3)]);
+        let mut cache = source.cache();
+        let entry = serde_json::to_string(&cache.data.summaries["fixture:en:Earth"]).unwrap().len() + "fixture:en:Earth".len();
+        cache.max_size = 2 * entry;
+        cache.dirty = true;
+        cache.save().unwrap();
+        assert!(cache.summary("fixture:en:Mercury").is_none());
+        assert!(cache.summary("fixture:en:Venus").is_some());
+        assert!(cache.summary("fixture:en:Earth").is_some());
+    }
+}
@@ -0,0 +1,381 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use study_guide_filler::cache::{CachedSource, SummaryCache};
+use study_guide_filler::error::Error;
+use study_guide_filler::fetch::{FetchJob, FetchOptions};
+use study_guide_filler::fill;
+use study_guide_filler::guide::{StudyGuide, TopicStatus};
+use study_guide_filler::import::{Column, ImportOptions, InputFormat};
+use study_guide_filler::rank::Context;
+use study_guide_filler::source::wikipedia::{Wikipedia, DEFAULT_API_URL};
+use study_guide_filler::source::SummarySource;
+use study_guide_filler::writer::{self, Format, WriteOptions};
+
+const USAGE: &str = "\
+Usage: study-guide-filler [fill <INPUT> [OPTIONS]]
+
+Without arguments the graphical app is started.
+
+Commands:
+  fill <INPUT>           Summarize every topic in INPUT (`-` reads stdin), a topic list,
+                         Markdown, CSV, TSV or JSON file, or the terms found in a Word or PDF
+                         document, told apart by extension and content
+
+Options:
+  -o, --output <FILE>    Where to write the guide (`-` or omitted writes stdout)
+  -f, --format <FORMAT>  Output format: text, markdown, html, pdf, docx, anki [default: from the output extension, else text]
+      --in-place         Write the summaries into the INPUT document itself (a topic list,
+                         Markdown or Word file), or into a copy of it at --output
+      --no-toc           Leave out the table of contents
+      --no-footer        Leave out the attribution footer
+      --font-size <PT>   PDF body text size [default: 11]
+      --margin <MM>      PDF page margin [default: 20]
+      --columns <N>      PDF text columns, 1 or 2 [default: 1]
+      --no-title-page    Start the PDF with the contents instead of a title page
+      --column <COLUMN>  CSV/TSV column holding the topics, a header name or number [default: a
+                         `topic`, `term` or `name` column, else the first]
+      --notes-column <COLUMN>
+                         CSV/TSV column with notes to keep alongside each topic
+  -l, --lang <CODE>      Wikipedia language [default: en]
+      --api-url <URL>    MediaWiki API endpoint, `{language}` is replaced by --lang
+  -j, --jobs <N>         Topics fetched in parallel [default: 4]
+      --no-rank          Take the first search result instead of the one that fits the other
+                         topics best
+      --offline          Only use summaries that are already cached
+      --no-cache         Neither read nor write the summary cache
+  -h, --help             Print this help
+  -V, --version          Print the version";
+
+struct FillOptions
+{
+    input: String,
+    output: Option<String>,
+    format: Option<Format>,
+    in_place: bool,
+    write_options: WriteOptions,
+    import_options: ImportOptions,
+    language: String,
+    api_url: String,
+    jobs: usize,
+    rank: bool,
+    offline: bool,
+    cache: bool,
+}
+
+enum Command
+{
+    Fill(FillOptions),
+    Help,
+    Version,
+}
+
+fn parse_args(args: Vec<String>) -> Result<Command, String>
+{
+    let mut args = args.into_iter();
+    match args.next().as_deref()
+    {
+        Some("fill") => (),
+        Some("-h" | "--help" | "help") => return Ok(Command::Help),
+        Some("-V" | "--version") => return Ok(Command::Version),
+        Some(other) => return Err(format!("unknown command `{}`", other)),
+        None => return Ok(Command::Help),
+    }
+
+    let mut options = FillOptions {
+        input: String::new(),
+        output: None,
+        format: None,
+        in_place: false,
+        write_options: WriteOptions::default(),
+        import_options: ImportOptions::default(),
+        language: "en".to_string(),
+        api_url: DEFAULT_API_URL.to_string(),
+        jobs: FetchOptions::default().concurrency,
+        rank: true,
+        offline: false,
+        cache: true,
+    };
+    let mut input = None;
+    while let Some(arg) = args.next()
+    {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("`{}` needs a value", name));
+        match arg.as_str()
+        {
+            "-o" | "--output" => options.output = Some(value(&arg)?),
+            "-f" | "--format" => {
+                let name = value(&arg)?;
+                options.format = Some(Format::from_name(&name).ok_or_else(|| format!("unknown format `{}`", name))?);
+            },
+            "--in-place" => options.in_place = true,
+            "--no-toc" => options.write_options.table_of_contents = false,
+            "--no-footer" => options.write_options.footer = false,
+            "--font-size" => {
+                let size = value(&arg)?;
+                options.write_options.layout.font_size = size.parse().ok().filter(|size| (6.0..=24.0).contains(size)).ok_or_else(|| format!("invalid font size `{}`", size))?;
+            },
+            "--margin" => {
+                let margin = value(&arg)?;
+                options.write_options.layout.margin = margin.parse().ok().filter(|margin| (0.0..=50.0).contains(margin)).ok_or_else(|| format!("invalid margin `{}`", margin))?;
+            },
+            "--columns" => {
+                let columns = value(&arg)?;
+                options.write_options.layout.columns = columns.parse().ok().filter(|columns| (1..=2).contains(columns)).ok_or_else(|| format!("invalid column count `{}`", columns))?;
+            },
+            "--no-title-page" => options.write_options.layout.title_page = false,
+            "--column" => options.import_options.column = Some(Column::parse(&value(&arg)?)),
+            "--notes-column" => options.import_options.notes_column = Some(Column::parse(&value(&arg)?)),
+            "-l" | "--lang" => options.language = value(&arg)?,
+            "--api-url" => options.api_url = value(&arg)?,
+            "-j" | "--jobs" => {
+                let jobs = value(&arg)?;
+                options.jobs = jobs.parse().ok().filter(|jobs| *jobs > 0).ok_or_else(|| format!("invalid job count `{}`", jobs))?;
+            },
+            "--offline" => options.offline = true,
+            "--no-cache" => options.cache = false,
+            "--no-rank" => options.rank = false,
+            "-h" | "--help" => return Ok(Command::Help),
+            _ if arg.starts_with('-') && arg != "-" => return Err(format!("unknown option `{}`", arg)),
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
+    }
+    options.input = input.ok_or("missing input file")?;
+    if options.in_place && options.input == "-" && options.output.is_none()
+    {
+        return Err("`--in-place` needs an input file or `--output`".to_string());
+    }
+    Ok(Command::Fill(options))
+}
+
+pub fn run(args: Vec<String>) -> ExitCode
+{
+    match parse_args(args)
+    {
+        Ok(Command::Fill(options)) => match fill(options)
+        {
+            Ok(0) => ExitCode::SUCCESS,
+            Ok(_) => ExitCode::from(1),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ExitCode::from(2)
+            },
+        },
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        },
+        Ok(Command::Version) => {
+            println!("study-guide-filler {}", env!("CARGO_PKG_VERSION"));
+            ExitCode::SUCCESS
+        },
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            ExitCode::from(2)
+        },
+    }
+}
+
+/// Fills the guide and returns how many topics could not be resolved.
+fn fill(options: FillOptions) -> Result<usize, Error>
+{
+    let mut content = Vec::new();
+    if options.input == "-"
+    {
+        io::stdin().read_to_end(&mut content)?;
+    }
+    else
+    {
+        content = fs::read(&options.input)?;
+    }
+    let path = PathBuf::from(&options.input);
+    let extension = path.extension().map(|extension| extension.to_string_lossy().to_string());
+    let input_format = InputFormat::detect(extension.as_deref(), &content);
+    if options.in_place && !fill::can_fill(input_format)
+    {
+        return Err(io::Error::other(format!("a {} can't be filled in place", input_format.name())).into());
+    }
+    let mut guide = input_format.import(&content, &options.import_options)?;
+    for warning in &guide.warnings
+    {
+        eprintln!("warning: {}", warning);
+    }
+    let unsure = drop_unconfirmed(&mut guide);
+    if !unsure.is_empty()
+    {
+        eprintln!("warning: left out {} unconfirmed topics: {}", unsure.len(), unsure.join("; "));
+    }
+    if options.input != "-" && guide.title.is_none()
+    {
+        guide.title = path.file_stem().map(|stem| stem.to_string_lossy().to_string());
+    }
+
+    let cache = if options.cache { SummaryCache::open_default() } else { SummaryCache::in_memory() };
+    let source = Arc::new(CachedSource::new(Wikipedia::new(&options.language).with_api_url(options.api_url), cache));
+    source.set_offline(options.offline);
+
+    guide.attribution = Some(source.attribution());
+    let entries = guide.entries.iter().cloned().enumerate().collect();
+    let fetch_options = FetchOptions { concurrency: options.jobs, rank: options.rank };
+    let mut job = FetchJob::topics(source.clone(), entries, Context::new(&guide), &fetch_options);
+    while let Some((i, entry)) = job.next_blocking()
+    {
+        let progress = format!("[{}/{}]", job.done(), job.total());
+        match &entry.status
+        {
+            TopicStatus::Found if entry.needs_review() => eprintln!("{} review     {} -> {} ({:.0}% confidence)", progress, entry.query, entry.chosen_title.as_deref().unwrap_or_default(), entry.confidence.unwrap_or_default() * 100.0),
+            TopicStatus::Found => eprintln!("{} ok         {} -> {}", progress, entry.query, entry.chosen_title.as_deref().unwrap_or_default()),
+            TopicStatus::NotFound => eprintln!("{} not found  {}", progress, entry.query),
+            TopicStatus::Ambiguous => eprintln!("{} ambiguous  {}: {}", progress, entry.query, entry.candidates.join(", ")),
+            status => eprintln!("{} failed     {}: {}", progress, entry.query, status),
+        }
+        if let Some(suggestion) = &entry.suggestion
+        {
+            eprintln!("{} did you mean \"{}\"?", " ".repeat(progress.len() + 11), suggestion);
+        }
+        guide.apply(i, entry);
+    }
+    if let Err(e) = source.cache().save()
+    {
+        eprintln!("warning: could not save the summary cache: {}", e);
+    }
+
+    if options.in_place
+    {
+        let filled = fill::fill(input_format, &content, &guide)?;
+        match options.output.as_deref().unwrap_or(&options.input)
+        {
+            "-" => io::stdout().lock().write_all(&filled)?,
+            path => writer::write_file(Path::new(path), &filled)?,
+        }
+    }
+    else
+    {
+        let output = options.output.filter(|output| output != "-");
+        write_guide(&guide, options.format, output.as_deref(), &options.write_options)?;
+    }
+
+    let failed = failures(&guide);
+    eprintln!("{} of {} topics resolved", guide.entries.len() - failed, guide.entries.len());
+    io::stderr().flush()?;
+    Ok(failed)
+}
+
+/// Nobody is there to confirm the unsure candidates found in a document, so they are left out.
+/// Returns their queries.
+fn drop_unconfirmed(guide: &mut StudyGuide) -> Vec<String>
+{
+    let unsure = guide.entries.iter().filter(|entry| !entry.enabled).map(|entry| entry.query.clone()).collect();
+    guide.entries.retain(|entry| entry.enabled);
+    unsure
+}
+
+/// Topics without a summary, including the ambiguous ones nobody picked a meaning for.
+fn failures(guide: &StudyGuide) -> usize
+{
+    guide.entries.iter().filter(|entry| entry.enabled && !entry.is_found()).count()
+}
+
+fn write_guide(guide: &StudyGuide, format: Option<Format>, output: Option<&str>, options: &WriteOptions) -> io::Result<()>
+{
+    let format = format
+        .or_else(|| output.as_ref()
+            .and_then(|output| PathBuf::from(output).extension().map(|extension| extension.to_string_lossy().to_string()))
+            .and_then(|extension| Format::from_extension(&extension)))
+        .unwrap_or_default();
+    if let Some(warning) = format.warning(guide)
+    {
+        eprintln!("warning: {}", warning);
+    }
+    match output
+    {
+        Some(path) => writer::write_file(Path::new(path), &format.to_bytes(guide, options)?),
+        None => format.write(guide, options, &mut io::stdout().lock()),
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String>
+    {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    /// Writes `content` to a file of its own in the temp directory and returns its path.
+    fn input(name: &str, content: &str) -> String
+    {
+        let path = std::env::temp_dir().join(format!("study-guide-filler-cli-{}-{}", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn parses_fill_options()
+    {
+        let Ok(Command::Fill(options)) = parse_args(args(&["fill", "topics.txt", "-o", "guide.txt", "--lang", "de", "-j", "2", "--offline", "--no-cache"]))
+        else
+        {
+            panic!("expected a fill command");
+        };
+        assert_eq!(options.input, "topics.txt");
+        assert_eq!(options.output.as_deref(), Some("guide.txt"));
+        assert_eq!(options.language, "de");
+        assert_eq!(options.jobs, 2);
+        assert!(options.offline);
+        assert!(!options.cache);
+        assert!(matches!(parse_args(args(&["--help"])), Ok(Command::Help)));
+        assert!(matches!(parse_args(args(&["-V"])), Ok(Command::Version)));
+    }
+
+    #[test]
+    fn rejects_bad_arguments()
+    {
+        let error = |list: &[&str]| parse_args(args(list)).err();
+        assert_eq!(error(&["fill", "topics.txt", "--verbose"]), Some("unknown option `--verbose`".to_string()));
+        assert_eq!(error(&["fetch", "topics.txt"]), Some("unknown command `fetch`".to_string()));
+        assert_eq!(error(&["topics.txt"]), Some("unknown command `topics.txt`".to_string()));
+        assert_eq!(error(&["topics.txt", "--verbose"]), Some("unknown command `topics.txt`".to_string()));
+        assert_eq!(error(&["fill"]), Some("missing input file".to_string()));
+        assert_eq!(error(&["fill", "topics.txt", "-j", "0"]), Some("invalid job count `0`".to_string()));
+        assert_eq!(error(&["fill", "topics.txt", "-o"]), Some("`-o` needs a value".to_string()));
+        assert_eq!(error(&["fill", "a.txt", "b.txt"]), Some("unexpected argument `b.txt`".to_string()));
+    }
+
+    #[test]
+    fn exit_code_says_how_the_fill_went()
+    {
+        let output = input("out.txt", "");
+        let fill = |input: &str| run(args(&["fill", input, "-o", &output, "--offline", "--no-cache"]));
+        assert_eq!(fill(&input("empty.txt", "")), ExitCode::SUCCESS);
+        assert_eq!(fill(&input("topics.txt", "Mitosis\nMeiosis\n")), ExitCode::from(1));
+        assert_eq!(fill("no-such-topics.txt"), ExitCode::from(2));
+        assert_eq!(run(args(&["fill", "--bogus"])), ExitCode::from(2));
+        assert_eq!(run(args(&["topics.txt"])), ExitCode::from(2));
+        assert_eq!(run(args(&["--help"])), ExitCode::SUCCESS);
+    }
+
+    #[test]
+    fn ambiguous_topics_count_as_failures()
+    {
+        let mut guide = StudyGuide::from_topics(vec!["Mercury".to_string(), "Mitosis".to_string(), "Meiosis".to_string()]);
+        guide.entries[0].set_meanings("Mercury", vec!["Mercury (planet)".to_string(), "Mercury (element)".to_string()]);
+        guide.entries[1].set_summary("Mitosis".to_string(), Default::default());
+        guide.entries[2].enabled = false;
+        assert_eq!(guide.entries[0].status, TopicStatus::Ambiguous);
+        assert_eq!(failures(&guide), 1);
+    }
+
+    #[test]
+    fn leaves_out_unconfirmed_topics()
+    {
+        let mut guide = StudyGuide::from_topics(vec!["Mitosis".to_string(), "Figure 2".to_string(), "Meiosis".to_string()]);
+        guide.entries[1].enabled = false;
+        assert_eq!(drop_unconfirmed(&mut guide), ["Figure 2"]);
+        assert_eq!(guide.entries.iter().map(|entry| entry.query.as_str()).collect::<Vec<_>>(), ["Mitosis", "Meiosis"]);
+    }
+}
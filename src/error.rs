@@ -0,0 +1,75 @@
+use std::fmt;
+use std::io;
+use crate::import::ImportError;
+use crate::project::ProjectError;
+use crate::source::SourceError;
+
+/// Anything that can go wrong between reading the topics and writing the guide.
+#[derive(Debug)]
+pub enum Error
+{
+    Import(ImportError),
+    Project(ProjectError),
+    Source(SourceError),
+    Io(io::Error),
+}
+
+impl fmt::Display for Error
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Error::Import(e) => write!(f, "{}", e),
+            Error::Project(e) => write!(f, "{}", e),
+            Error::Source(e) => write!(f, "{}", e),
+            Error::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)>
+    {
+        match self
+        {
+            Error::Import(e) => Some(e),
+            Error::Project(e) => Some(e),
+            Error::Source(e) => Some(e),
+            Error::Io(e) => Some(e),
+        }
+    }
+}
+
+impl From<ImportError> for Error
+{
+    fn from(e: ImportError) -> Error
+    {
+        Error::Import(e)
+    }
+}
+
+impl From<ProjectError> for Error
+{
+    fn from(e: ProjectError) -> Error
+    {
+        Error::Project(e)
+    }
+}
+
+impl From<SourceError> for Error
+{
+    fn from(e: SourceError) -> Error
+    {
+        Error::Source(e)
+    }
+}
+
+impl From<io::Error> for Error
+{
+    fn from(e: io::Error) -> Error
+    {
+        Error::Io(e)
+    }
+}
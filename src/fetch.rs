@@ -0,0 +1,346 @@
+use std::collections::{HashSet, VecDeque};
+use std::future::Future;
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use crate::guide::TopicEntry;
+use crate::rank::{self, Context};
+use crate::source::{Cancel, LookupOptions, SummarySource};
+use crate::spelling;
+
+pub type EntryUpdate = (usize, TopicEntry);
+
+#[derive(Clone, Debug)]
+pub struct FetchOptions
+{
+    /// Number of topics fetched at the same time.
+    pub concurrency: usize,
+    /// Pick the search result that fits the rest of the topics best instead of the first one.
+    pub rank: bool,
+}
+
+impl Default for FetchOptions
+{
+    fn default() -> FetchOptions
+    {
+        FetchOptions {
+            concurrency: 4,
+            rank: true,
+        }
+    }
+}
+
+/// Handle to topics being fetched in the background, on worker threads natively
+/// and on local tasks on the web.
+///
+/// Dropping the handle cancels the workers and discards anything they send afterwards.
+pub struct FetchJob
+{
+    receiver: Receiver<EntryUpdate>,
+    cancel: Cancel,
+    /// Entries a worker is fetching right now.
+    active: Arc<Mutex<HashSet<usize>>>,
+    total: usize,
+    done: usize,
+    disconnected: bool,
+}
+
+impl FetchJob
+{
+    /// Searches every entry and fetches the summary of its best result, ranked against the other
+    /// topics in `context` if `options.rank` is set, running up to `options.concurrency` topics in parallel.
+    pub fn topics<S>(source: Arc<S>, entries: Vec<EntryUpdate>, context: Context, options: &FetchOptions) -> FetchJob
+    where
+        S: SummarySource + Send + Sync + 'static,
+    {
+        let total = entries.len();
+        let workers = options.concurrency.clamp(1, total.max(1));
+        let queue = Arc::new(Mutex::new(VecDeque::from(entries)));
+        let context = Arc::new(options.rank.then_some(context));
+        FetchJob::spawn(total, workers, move |sender, cancel, active| {
+            let source = source.clone();
+            let queue = queue.clone();
+            let context = context.clone();
+            async move {
+                while !cancel.is_cancelled()
+                {
+                    let next = queue.lock().ok().and_then(|mut queue| queue.pop_front());
+                    let Some((i, mut entry)) = next else { break };
+                    set_active(&active, i, true);
+                    learn_topic(source.as_ref(), &mut entry, context.as_ref().as_ref(), &cancel).await;
+                    set_active(&active, i, false);
+                    // What was cut short by the cancel is left as it was.
+                    if cancel.is_cancelled() || sender.send((i, entry)).is_err()
+                    {
+                        break;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Fetches the summary of `title` for an entry that was already searched.
+    pub fn summary<S>(source: Arc<S>, i: usize, entry: TopicEntry, title: String) -> FetchJob
+    where
+        S: SummarySource + Send + Sync + 'static,
+    {
+        FetchJob::spawn(1, 1, move |sender, cancel, active| {
+            let source = source.clone();
+            let mut entry = entry.clone();
+            let title = title.clone();
+            async move {
+                set_active(&active, i, true);
+                entry.confidence = None;
+                let options = LookupOptions { cancel: cancel.clone(), ..entry.lookup() };
+                create_summary(source.as_ref(), &mut entry, title, &options).await;
+                set_active(&active, i, false);
+                if !cancel.is_cancelled()
+                {
+                    let _ = sender.send((i, entry));
+                }
+            }
+        })
+    }
+
+    fn spawn<F, W>(total: usize, workers: usize, worker: W) -> FetchJob
+    where
+        W: Fn(Sender<EntryUpdate>, Cancel, Arc<Mutex<HashSet<usize>>>) -> F + Send + Sync + 'static,
+        F: Future<Output = ()> + 'static,
+    {
+        let (sender, receiver) = channel();
+        let cancel = Cancel::default();
+        let active = Arc::new(Mutex::new(HashSet::new()));
+        let worker = Arc::new(worker);
+        for _ in 0..workers
+        {
+            let worker = worker.clone();
+            let sender = sender.clone();
+            let cancel = cancel.clone();
+            let active = active.clone();
+            spawn_task(move || worker(sender, cancel, active));
+        }
+        FetchJob {
+            receiver,
+            cancel,
+            active,
+            total,
+            done: 0,
+            disconnected: false,
+        }
+    }
+
+    /// Returns every update that has arrived since the last call.
+    pub fn poll(&mut self) -> Vec<EntryUpdate>
+    {
+        let mut updates = Vec::new();
+        loop
+        {
+            match self.receiver.try_recv()
+            {
+                Ok(update) => updates.push(update),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.disconnected = true;
+                    break;
+                },
+            }
+        }
+        self.done += updates.len();
+        updates
+    }
+
+    /// Blocks until the next update arrives, or returns `None` once every worker is done.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn next_blocking(&mut self) -> Option<EntryUpdate>
+    {
+        if self.is_finished()
+        {
+            return None;
+        }
+        match self.receiver.recv()
+        {
+            Ok(update) => {
+                self.done += 1;
+                Some(update)
+            },
+            Err(_) => {
+                self.disconnected = true;
+                None
+            },
+        }
+    }
+
+    /// Stops the outstanding requests: no worker sends another request or waits out a backoff,
+    /// and topics still being looked up are not sent. Results already received are kept.
+    pub fn cancel(&self)
+    {
+        self.cancel.cancel();
+    }
+
+    /// Whether entry `i` is being looked up right now, rather than waiting its turn or done.
+    pub fn is_fetching(&self, i: usize) -> bool
+    {
+        self.active.lock().is_ok_and(|active| active.contains(&i))
+    }
+
+    pub fn is_finished(&self) -> bool
+    {
+        self.disconnected || self.done >= self.total
+    }
+
+    pub fn done(&self) -> usize
+    {
+        self.done
+    }
+
+    pub fn total(&self) -> usize
+    {
+        self.total
+    }
+
+    pub fn progress(&self) -> f32
+    {
+        if self.total == 0 { 1.0 } else { self.done as f32 / self.total as f32 }
+    }
+}
+
+/// A single value worked out in the background, see [`spawn_task`].
+pub struct Task<T>
+{
+    receiver: Receiver<T>,
+}
+
+impl<T: Send + 'static> Task<T>
+{
+    pub fn spawn<F, B>(task: B) -> Task<T>
+    where
+        B: FnOnce() -> F + Send + 'static,
+        F: Future<Output = T> + 'static,
+    {
+        let (sender, receiver) = channel();
+        spawn_task(move || {
+            let future = task();
+            async move {
+                let _ = sender.send(future.await);
+            }
+        });
+        Task { receiver }
+    }
+
+    /// The value, once it is ready.
+    pub fn poll(&self) -> Option<T>
+    {
+        self.receiver.try_recv().ok()
+    }
+}
+
+/// Runs the future built by `task` in the background: on its own thread natively,
+/// as a local task on the web.
+pub fn spawn_task<F, T>(task: T)
+where
+    T: FnOnce() -> F + Send + 'static,
+    F: Future<Output = ()> + 'static,
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    std::thread::spawn(move || pollster::block_on(task()));
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(task());
+}
+
+fn set_active(active: &Mutex<HashSet<usize>>, i: usize, fetching: bool)
+{
+    if let Ok(mut active) = active.lock()
+    {
+        if fetching
+        {
+            active.insert(i);
+        }
+        else
+        {
+            active.remove(&i);
+        }
+    }
+}
+
+impl Drop for FetchJob
+{
+    fn drop(&mut self)
+    {
+        self.cancel();
+    }
+}
+
+async fn learn_topic<S: SummarySource>(source: &S, entry: &mut TopicEntry, context: Option<&Context>, cancel: &Cancel)
+{
+    let options = LookupOptions { cancel: cancel.clone(), ..entry.lookup() };
+    if let Some(title) = entry.pinned_title.clone()
+    {
+        entry.set_candidates(vec![title.clone()]);
+        create_summary(source, entry, title, &options).await;
+        return;
+    }
+    match source.search(entry.query.as_str(), &options).await
+    {
+        Ok(results) => {
+            if !results.iter().any(|title| spelling::same(&entry.query, title))
+            {
+                let suggestions = source.suggestions(&entry.query, &options).await.unwrap_or_default();
+                entry.suggestion = spelling::correction(&entry.query, results.iter().chain(&suggestions));
+            }
+            match context
+            {
+                Some(context) if results.len() > 1 => {
+                    let ranking = rank::rank(source, &entry.query, results, context, &options).await;
+                    entry.set_candidates(ranking.candidates);
+                    entry.confidence = Some(ranking.confidence);
+                },
+                _ => entry.set_candidates(results),
+            }
+            if let Some(title) = entry.candidates.first().cloned()
+            {
+                create_summary(source, entry, title, &options).await;
+            }
+        },
+        Err(e) => entry.fail(e.to_string()),
+    }
+}
+
+async fn create_summary<S: SummarySource>(source: &S, entry: &mut TopicEntry, title: String, options: &LookupOptions)
+{
+    match source.summary(&title, options).await
+    {
+        Ok(summary) if summary.disambiguation => {
+            match source.meanings(&title, options).await
+            {
+                Ok(meanings) => entry.set_meanings(&title, meanings),
+                Err(e) => entry.fail(e.to_string()),
+            }
+        },
+        Ok(summary) => entry.set_summary(title, summary),
+        Err(e) => entry.fail(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::source::fixture::FixtureSource;
+
+    fn learn(source: &FixtureSource, query: &str) -> TopicEntry
+    {
+        let mut entry = TopicEntry::new(query);
+        pollster::block_on(learn_topic(source, &mut entry, None, &Cancel::default()));
+        entry
+    }
+
+    #[test]
+    fn suggests_a_spelling_only_when_no_result_matches()
+    {
+        let source = FixtureSource::new()
+            .with_page("Hundred Years' War", "A war between England and France.")
+            .with_page("Cell", "The basic unit of life.")
+            .with_page("Cells", "A novel.");
+        assert_eq!(learn(&source, "The Hundreds Year War").suggestion.as_deref(), Some("Hundred Years' War"));
+        assert_eq!(learn(&source, "cell").suggestion, None);
+    }
+}
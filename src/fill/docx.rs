@@ -0,0 +1,323 @@
+use std::io::{self, Cursor, Read, Write};
+use std::ops::Range;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+use crate::guide::{StudyGuide, TopicEntry};
+use crate::xml::{tags, unescape};
+use crate::parser;
+use crate::writer::docx::escape;
+
+const DOCUMENT: &str = "word/document.xml";
+
+#[derive(Default)]
+struct Paragraph
+{
+    /// Byte range of the paragraph in the XML.
+    range: Range<usize>,
+    text: String,
+    /// Byte ranges of the text inside its `w:t` elements.
+    texts: Vec<Range<usize>>,
+}
+
+/// Fills a Word document, changing only `word/document.xml` and copying the other parts as they are.
+pub(super) fn fill(original: &[u8], guide: &StudyGuide) -> io::Result<Vec<u8>>
+{
+    edit(original, |xml| fill_xml(xml, guide))
+}
+
+/// Replaces `from` with `to` in paragraph `origin`, keeping the formatting of the run it starts in.
+pub(super) fn rename(original: &[u8], origin: usize, from: &str, to: &str) -> io::Result<Vec<u8>>
+{
+    edit(original, |xml| {
+        let paragraph = paragraphs(xml).into_iter().nth(origin).ok_or_else(|| super::not_on_line(from, origin))?;
+        let edits = rename_text(xml, &paragraph, from, to).ok_or_else(|| super::not_on_line(from, origin))?;
+        Ok(apply(xml, edits))
+    })
+}
+
+/// Rewrites `word/document.xml` with `change`, copying the other parts as they are.
+fn edit(original: &[u8], change: impl FnOnce(&str) -> io::Result<String>) -> io::Result<Vec<u8>>
+{
+    let mut archive = ZipArchive::new(Cursor::new(original)).map_err(io::Error::other)?;
+    let mut xml = String::new();
+    archive.by_name(DOCUMENT).map_err(io::Error::other)?.read_to_string(&mut xml)?;
+    let xml = change(&xml)?;
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for i in 0..archive.len()
+    {
+        let file = archive.by_index_raw(i).map_err(io::Error::other)?;
+        if file.name() == DOCUMENT
+        {
+            zip.start_file(DOCUMENT, SimpleFileOptions::default().compression_method(CompressionMethod::Deflated)).map_err(io::Error::other)?;
+            zip.write_all(xml.as_bytes())?;
+        }
+        else
+        {
+            zip.raw_copy_file(file).map_err(io::Error::other)?;
+        }
+    }
+    Ok(zip.finish().map_err(io::Error::other)?.into_inner())
+}
+
+fn fill_xml(xml: &str, guide: &StudyGuide) -> io::Result<String>
+{
+    let answers = super::answers(guide);
+    let paragraphs = paragraphs(xml);
+    if let Some((origin, entry)) = answers.iter().find(|(origin, entry)| !paragraphs.get(**origin).is_some_and(|paragraph| holds(paragraph, &entry.query)))
+    {
+        return Err(super::not_on_line(&entry.query, *origin));
+    }
+
+    let mut edits = Vec::<(Range<usize>, String)>::new();
+    for (i, paragraph) in paragraphs.iter().enumerate()
+    {
+        let Some(entry) = answers.get(&i) else { continue };
+        if parser::split_blank(&paragraph.text).1.is_some()
+        {
+            edits.extend(fill_blank(xml, paragraph, &super::paragraphs(entry).join(" ")));
+            continue;
+        }
+
+        // Empty or blank paragraphs left under the topic are written over.
+        let mut end = i + 1;
+        for (next, paragraph) in paragraphs.iter().enumerate().skip(i + 1)
+        {
+            if parser::is_blank(&paragraph.text)
+            {
+                end = next + 1;
+            }
+            else if !paragraph.text.trim().is_empty()
+            {
+                break;
+            }
+        }
+        let range = if end > i + 1
+        {
+            paragraphs[i + 1].range.start..paragraphs[end - 1].range.end
+        }
+        else
+        {
+            paragraph.range.end..paragraph.range.end
+        };
+        edits.push((range, answer(entry)));
+    }
+    Ok(apply(xml, edits))
+}
+
+/// Whether the topic is still in the paragraph, so the summary doesn't end up under another one
+/// if the document changed or its paragraphs were counted differently.
+fn holds(paragraph: &Paragraph, query: &str) -> bool
+{
+    paragraph.text.split_whitespace().collect::<Vec<_>>().join(" ").contains(query)
+}
+
+/// Makes the `edits`, given in document order, to `xml`.
+fn apply(xml: &str, edits: Vec<(Range<usize>, String)>) -> String
+{
+    let mut edited = String::with_capacity(xml.len());
+    let mut at = 0;
+    for (range, text) in edits
+    {
+        edited.push_str(&xml[at..range.start]);
+        edited.push_str(&text);
+        at = range.end;
+    }
+    edited.push_str(&xml[at..]);
+    edited
+}
+
+/// The paragraphs of the document body, counted the same way the importer does.
+fn paragraphs(xml: &str) -> Vec<Paragraph>
+{
+    let mut paragraphs = Vec::new();
+    let mut paragraph = Paragraph::default();
+    let mut in_text = false;
+    for tag in tags(xml)
+    {
+        match (tag.name, tag.closing)
+        {
+            ("w:p", false) if !tag.empty => paragraph = Paragraph { range: tag.start..tag.start, ..Paragraph::default() },
+            ("w:p", true) => {
+                paragraph.range.end = tag.end;
+                paragraphs.push(std::mem::take(&mut paragraph));
+            },
+            ("w:t", false) => in_text = !tag.empty,
+            ("w:t", true) => in_text = false,
+            ("w:tab", false) | ("w:br", false) => paragraph.text.push(' '),
+            ("#text", _) if in_text => {
+                paragraph.text.push_str(&unescape(tag.text));
+                paragraph.texts.push(tag.start..tag.end);
+            },
+            _ => (),
+        }
+    }
+    paragraphs
+}
+
+/// Replaces the blank at the end of a paragraph, like `Mitosis: ______`, with the summary.
+/// The blank may be split over several runs, the first of them gets the summary.
+fn fill_blank(xml: &str, paragraph: &Paragraph, summary: &str) -> Vec<(Range<usize>, String)>
+{
+    let is_blank = |c: char| parser::BLANKS.contains(&c) || c.is_whitespace();
+    let texts = paragraph.texts.iter().map(|range| unescape(&xml[range.clone()])).collect::<Vec<_>>();
+    let mut first = texts.len();
+    while first > 0 && texts[first - 1].trim_end().ends_with(parser::BLANKS)
+    {
+        first -= 1;
+        if !texts[first].trim_start_matches(is_blank).is_empty()
+        {
+            break;
+        }
+    }
+
+    let mut edits = Vec::new();
+    for (i, text) in texts.iter().enumerate().skip(first)
+    {
+        let kept = text.trim_end_matches(is_blank);
+        let text = if i > first
+        {
+            String::new()
+        }
+        else
+        {
+            let before = if kept.is_empty() { texts[..i].concat() } else { kept.to_string() };
+            let space = if before.is_empty() || before.ends_with(char::is_whitespace) { "" } else { " " };
+            format!("{}{}{}", kept, space, summary)
+        };
+        edits.push((paragraph.texts[i].clone(), escape(&text)));
+    }
+    edits
+}
+
+/// The edits replacing `from` in the paragraph's text, which may be split over several runs: the
+/// run it starts in gets `to`, the rest of it is taken out of the runs after.
+fn rename_text(xml: &str, paragraph: &Paragraph, from: &str, to: &str) -> Option<Vec<(Range<usize>, String)>>
+{
+    let texts = paragraph.texts.iter().map(|range| unescape(&xml[range.clone()])).collect::<Vec<_>>();
+    let start = texts.concat().find(from)?;
+    let end = start + from.len();
+
+    let mut edits = Vec::new();
+    let mut offset = 0;
+    for (i, text) in texts.iter().enumerate()
+    {
+        let (run_start, run_end) = (offset, offset + text.len());
+        offset = run_end;
+        if run_end <= start || run_start >= end
+        {
+            continue;
+        }
+        let before = &text[..start.saturating_sub(run_start)];
+        let after = &text[end.min(run_end) - run_start..];
+        let replaced = if run_start <= start { to } else { "" };
+        edits.push((paragraph.texts[i].clone(), escape(&format!("{}{}{}", before, replaced, after))));
+    }
+    Some(edits)
+}
+
+fn answer(entry: &TopicEntry) -> String
+{
+    let mut xml = String::new();
+    for paragraph in super::paragraphs(entry)
+    {
+        xml.push_str(&format!(
+            r#"<w:p><w:pPr><w:ind w:left="360"/></w:pPr><w:r><w:t xml:space="preserve">{}</w:t></w:r></w:p>"#,
+            escape(paragraph),
+        ));
+    }
+    if let (Some(title), Some(url)) = (&entry.chosen_title, &entry.url)
+    {
+        xml.push_str(&format!(
+            r#"<w:p><w:pPr><w:ind w:left="360"/></w:pPr><w:r><w:rPr><w:i/></w:rPr><w:t xml:space="preserve">Article: {} ({})</w:t></w:r></w:p>"#,
+            escape(title),
+            escape(url),
+        ));
+    }
+    xml
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::import::InputFormat;
+
+    fn docx(body: &str) -> Vec<u8>
+    {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("word/styles.xml", SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"<w:styles/>").unwrap();
+        zip.start_file(DOCUMENT, SimpleFileOptions::default()).unwrap();
+        zip.write_all(format!("<w:document><w:body>{}</w:body></w:document>", body).as_bytes()).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn document(content: &[u8]) -> String
+    {
+        let mut xml = String::new();
+        ZipArchive::new(Cursor::new(content)).unwrap().by_name(DOCUMENT).unwrap().read_to_string(&mut xml).unwrap();
+        xml
+    }
+
+    fn answered(content: &[u8], topic: &str, summary: &str) -> StudyGuide
+    {
+        let mut guide = InputFormat::Docx.import(content, &Default::default()).unwrap();
+        let entry = guide.entries.iter_mut().find(|entry| entry.query == topic).unwrap();
+        entry.summary = Some(summary.to_string());
+        guide
+    }
+
+    #[test]
+    fn fills_a_blank_split_over_runs()
+    {
+        let original = docx(r#"<w:p><w:r><w:t>Mitosis: __</w:t></w:r><w:r><w:t>____</w:t></w:r></w:p>"#);
+        let filled = fill(&original, &answered(&original, "Mitosis", "Cell division & more.")).unwrap();
+        assert_eq!(
+            document(&filled),
+            r#"<w:document><w:body><w:p><w:r><w:t>Mitosis: Cell division &amp; more.</w:t></w:r><w:r><w:t></w:t></w:r></w:p></w:body></w:document>"#,
+        );
+    }
+
+    #[test]
+    fn writes_over_blank_paragraphs_under_the_topic()
+    {
+        let original = docx(r#"<w:p><w:r><w:t>Mitosis:</w:t></w:r></w:p><w:p><w:r><w:t>______</w:t></w:r></w:p><w:p><w:r><w:t>Meiosis</w:t></w:r></w:p>"#);
+        let xml = document(&fill(&original, &answered(&original, "Mitosis", "Cell division.")).unwrap());
+        assert!(xml.contains("Cell division."), "{}", xml);
+        assert!(!xml.contains("______"), "{}", xml);
+        assert!(xml.contains("Meiosis"), "{}", xml);
+    }
+
+    #[test]
+    fn fills_the_topic_after_a_table()
+    {
+        let table = r#"<w:tbl><w:tr><w:tc><w:p><w:r><w:t>Cell</w:t></w:r></w:p></w:tc><w:tc><w:p/><w:p><w:r><w:t>Notes</w:t></w:r></w:p></w:tc></w:tr></w:tbl>"#;
+        let original = docx(&format!(r#"{}<w:p><w:r><w:t>Mitosis: ____</w:t></w:r></w:p><w:p><w:r><w:t>Meiosis: ____</w:t></w:r></w:p>"#, table));
+        let filled = fill(&original, &answered(&original, "Meiosis", "Cell division.")).unwrap();
+        let xml = document(&filled);
+        assert!(xml.contains("<w:t>Mitosis: ____</w:t>"), "{}", xml);
+        assert!(xml.contains("<w:t>Meiosis: Cell division.</w:t>"), "{}", xml);
+    }
+
+    #[test]
+    fn refuses_to_fill_a_paragraph_without_the_topic()
+    {
+        let original = docx(r#"<w:p><w:r><w:t>Mitosis: ____</w:t></w:r></w:p><w:p><w:r><w:t>Meiosis: ____</w:t></w:r></w:p>"#);
+        let mut guide = answered(&original, "Meiosis", "Cell division.");
+        guide.entries[1].origin = Some(0);
+        assert_eq!(fill(&original, &guide).unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn renames_across_runs()
+    {
+        let original = docx(r#"<w:p><w:r><w:rPr><w:b/></w:rPr><w:t>Mei</w:t></w:r><w:r><w:t>ossis: the rest</w:t></w:r></w:p>"#);
+        let renamed = rename(&original, 0, "Meiossis", "Meiosis").unwrap();
+        assert_eq!(
+            document(&renamed),
+            r#"<w:document><w:body><w:p><w:r><w:rPr><w:b/></w:rPr><w:t>Meiosis</w:t></w:r><w:r><w:t>: the rest</w:t></w:r></w:p></w:body></w:document>"#,
+        );
+        assert_eq!(rename(&original, 1, "Meiossis", "Meiosis").unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,176 @@
+use std::io;
+use crate::guide::StudyGuide;
+use crate::import::markdown::list_item;
+use crate::parser;
+use crate::writer::markdown::escape;
+
+/// Fills a topic list or Markdown file line by line, keeping its line endings.
+pub(super) fn fill(content: &str, guide: &StudyGuide, markdown: bool) -> String
+{
+    let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let answers = super::answers(guide);
+    let lines = content.lines().collect::<Vec<_>>();
+    let mut filled = Vec::<String>::with_capacity(lines.len());
+    let mut i = 0;
+    while i < lines.len()
+    {
+        let line = lines[i];
+        i += 1;
+        let Some(entry) = answers.get(&(i - 1)) else {
+            filled.push(line.to_string());
+            continue;
+        };
+        let summary = super::paragraphs(entry)
+            .into_iter()
+            .map(|paragraph| if markdown { escape(paragraph) } else { paragraph.to_string() })
+            .collect::<Vec<_>>();
+
+        if let (_, Some(blank)) = parser::split_blank(line)
+        {
+            let at = line.rfind(blank).unwrap_or(line.len());
+            filled.push(format!("{}{}{}", &line[..at], summary.join(" "), &line[at + blank.len()..]));
+            continue;
+        }
+        filled.push(line.to_string());
+
+        // Blank lines left under the topic are written over, the empty lines after them kept.
+        let mut end = i;
+        for (next, line) in lines.iter().enumerate().skip(i)
+        {
+            if is_placeholder(line)
+            {
+                end = next + 1;
+            }
+            else if !line.trim().is_empty()
+            {
+                break;
+            }
+        }
+        i = end;
+
+        let indent = parser::indent_width(line);
+        if !markdown
+        {
+            filled.extend(summary.iter().map(|paragraph| format!("{}    {}", " ".repeat(indent), paragraph)));
+            continue;
+        }
+        // Under a list item the summary is indented to the item's text so it stays part of it.
+        let column = match list_item(line)
+        {
+            Some(_) => indent + line.split_whitespace().next().map_or(0, str::len) + 1,
+            None => 0,
+        };
+        let pad = " ".repeat(column);
+        for paragraph in &summary
+        {
+            filled.push(String::new());
+            filled.push(format!("{}{}", pad, paragraph));
+        }
+        if let (Some(title), Some(url)) = (&entry.chosen_title, &entry.url)
+        {
+            filled.push(String::new());
+            filled.push(format!("{}*Article: [{}](<{}>)*", pad, escape(title), url));
+        }
+        if lines.get(i).is_some_and(|next| !next.trim().is_empty() && list_item(next).is_none())
+        {
+            filled.push(String::new());
+        }
+    }
+
+    let mut filled = filled.join(newline);
+    if content.ends_with('\n')
+    {
+        filled.push_str(newline);
+    }
+    filled
+}
+
+/// Replaces the first `from` on line `origin` with `to`, keeping the line endings.
+pub(super) fn rename(content: &str, origin: usize, from: &str, to: &str) -> io::Result<String>
+{
+    let mut renamed = String::with_capacity(content.len());
+    let mut found = false;
+    for (i, line) in content.split_inclusive('\n').enumerate()
+    {
+        match line.find(from).filter(|_| i == origin)
+        {
+            Some(at) => {
+                renamed.push_str(&format!("{}{}{}", &line[..at], to, &line[at + from.len()..]));
+                found = true;
+            },
+            None => renamed.push_str(line),
+        }
+    }
+    if !found
+    {
+        return Err(super::not_on_line(from, origin));
+    }
+    Ok(renamed)
+}
+
+/// A line like `______`, or a list item of one, left to write the answer in.
+fn is_placeholder(line: &str) -> bool
+{
+    parser::is_blank(line) || list_item(line).is_some_and(parser::is_blank)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::import::markdown;
+
+    fn answer(mut guide: StudyGuide, topic: &str, summary: &str) -> StudyGuide
+    {
+        let entry = guide.entries.iter_mut().find(|entry| entry.query == topic).unwrap();
+        entry.summary = Some(summary.to_string());
+        guide
+    }
+
+    #[test]
+    fn fills_the_blank_on_the_line()
+    {
+        let content = "Mitosis: ______\r\nMeiosis\r\n";
+        let guide = answer(parser::parse(content), "Mitosis", "Cell division.\nSecond part.");
+        assert_eq!(fill(content, &guide, false), "Mitosis: Cell division. Second part.\r\nMeiosis\r\n");
+    }
+
+    #[test]
+    fn writes_over_blank_lines_under_the_topic()
+    {
+        let content = "Mitosis:\n______\n\nMeiosis\n";
+        let guide = answer(parser::parse(content), "Mitosis", "Cell division.");
+        assert_eq!(guide.entries[0].query, "Mitosis");
+        assert_eq!(fill(content, &guide, false), "Mitosis:\n    Cell division.\n\nMeiosis\n");
+    }
+
+    #[test]
+    fn fills_markdown_under_the_list_item()
+    {
+        let content = "- Mitosis\n- Meiosis";
+        let mut guide = answer(markdown::parse(content), "Mitosis", "Cell *division*.");
+        guide.entries[0].chosen_title = Some("Mitosis".to_string());
+        guide.entries[0].url = Some("https://en.wikipedia.org/wiki/Mitosis".to_string());
+        assert_eq!(
+            fill(content, &guide, true),
+            "- Mitosis\n\n  Cell \\*division\\*.\n\n  *Article: [Mitosis](<https://en.wikipedia.org/wiki/Mitosis>)*\n- Meiosis",
+        );
+    }
+
+    #[test]
+    fn unchecked_topics_are_left_alone()
+    {
+        let content = "Mitosis: ______\n";
+        let mut guide = answer(parser::parse(content), "Mitosis", "Cell division.");
+        guide.entries[0].enabled = false;
+        assert_eq!(fill(content, &guide, false), content);
+    }
+
+    #[test]
+    fn renames_on_the_line()
+    {
+        let content = "Mitosis\r\nMeiossis: ______\r\n";
+        assert_eq!(rename(content, 1, "Meiossis", "Meiosis").unwrap(), "Mitosis\r\nMeiosis: ______\r\n");
+        assert_eq!(rename(content, 0, "Meiossis", "Meiosis").unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::io;
+use crate::guide::{StudyGuide, TopicEntry};
+use crate::import::InputFormat;
+
+mod docx;
+mod lines;
+
+/// Whether documents of this format can have their summaries filled in place.
+pub fn can_fill(format: InputFormat) -> bool
+{
+    matches!(format, InputFormat::Text | InputFormat::Markdown | InputFormat::Docx)
+}
+
+/// Writes each summary of `guide` back into `original`, the document the topics were imported
+/// from: into the blank after the topic, like `Mitosis: ______`, over the blank lines under it,
+/// or else just below it. Everything else in the document is kept as it was.
+pub fn fill(format: InputFormat, original: &[u8], guide: &StudyGuide) -> io::Result<Vec<u8>>
+{
+    match format
+    {
+        InputFormat::Text => Ok(lines::fill(&String::from_utf8_lossy(original), guide, false).into_bytes()),
+        InputFormat::Markdown => Ok(lines::fill(&String::from_utf8_lossy(original), guide, true).into_bytes()),
+        InputFormat::Docx => docx::fill(original, guide),
+        _ => Err(io::Error::new(io::ErrorKind::Unsupported, format!("a {} can't be filled in place", format.name()))),
+    }
+}
+
+/// Replaces the topic `from` with `to` on line or paragraph `origin` of `original`, e.g. to fix
+/// its spelling in the document it was imported from.
+pub fn rename(format: InputFormat, original: &[u8], origin: usize, from: &str, to: &str) -> io::Result<Vec<u8>>
+{
+    match format
+    {
+        InputFormat::Text | InputFormat::Markdown => lines::rename(&String::from_utf8_lossy(original), origin, from, to).map(String::into_bytes),
+        InputFormat::Docx => docx::rename(original, origin, from, to),
+        _ => Err(io::Error::new(io::ErrorKind::Unsupported, format!("a {} can't be edited in place", format.name()))),
+    }
+}
+
+fn not_on_line(topic: &str, origin: usize) -> io::Error
+{
+    io::Error::new(io::ErrorKind::NotFound, format!("\"{}\" is no longer on line or paragraph {}", topic, origin + 1))
+}
+
+/// The entries with a summary to fill in, by the line or paragraph they were read from.
+fn answers(guide: &StudyGuide) -> HashMap<usize, &TopicEntry>
+{
+    guide.entries.iter()
+        .filter(|entry| entry.enabled && entry.summary.is_some())
+        .filter_map(|entry| entry.origin.map(|origin| (origin, entry)))
+        .collect()
+}
+
+fn paragraphs(entry: &TopicEntry) -> Vec<&str>
+{
+    entry.summary.as_deref()
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|paragraph| !paragraph.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn only_source_documents_are_filled()
+    {
+        assert!(can_fill(InputFormat::Markdown));
+        assert!(!can_fill(InputFormat::Csv));
+        let error = fill(InputFormat::Json, b"[]", &StudyGuide::default()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+        let error = rename(InputFormat::Pdf, b"", 0, "a", "b").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+    }
+}
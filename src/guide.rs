@@ -0,0 +1,428 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::rank::LOW_CONFIDENCE;
+use crate::source::{Attribution, LookupOptions, Summary};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum TopicStatus
+{
+    #[default]
+    Pending,
+    Found,
+    NotFound,
+    /// The topic matches several articles and the user has to pick one.
+    Ambiguous,
+    Failed(String),
+    /// The fetch was cancelled before the topic's turn. Neither done nor failed.
+    Cancelled,
+}
+
+impl TopicStatus
+{
+    /// Short label for the topic list.
+    pub fn label(&self) -> &'static str
+    {
+        match self
+        {
+            TopicStatus::Pending => "pending",
+            TopicStatus::Found => "ok",
+            TopicStatus::NotFound => "not found",
+            TopicStatus::Ambiguous => "ambiguous",
+            TopicStatus::Failed(_) => "error",
+            TopicStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether fetching the topic again might turn out differently.
+    pub fn can_retry(&self) -> bool
+    {
+        matches!(self, TopicStatus::NotFound | TopicStatus::Failed(_) | TopicStatus::Cancelled)
+    }
+}
+
+impl fmt::Display for TopicStatus
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            TopicStatus::Pending => write!(f, "Pending"),
+            TopicStatus::Found => write!(f, "Found"),
+            TopicStatus::NotFound => write!(f, "No results"),
+            TopicStatus::Ambiguous => write!(f, "Several articles match, choose one"),
+            TopicStatus::Failed(message) => write!(f, "Error: {}", message),
+            TopicStatus::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TopicEntry
+{
+    pub query: String,
+    pub enabled: bool,
+    pub candidates: Vec<String>,
+    /// A corrected spelling of `query` to offer the user, like "Hundred Years' War".
+    pub suggestion: Option<String>,
+    pub chosen_title: Option<String>,
+    /// How sure the automatic pick of `chosen_title` is, from 0 to 1. `None` when there was no
+    /// choice to make or the user made it.
+    pub confidence: Option<f32>,
+    pub summary: Option<String>,
+    /// Link to the page the summary came from.
+    pub url: Option<String>,
+    /// Revision of the page when the summary was fetched.
+    pub revision: Option<u64>,
+    pub status: TopicStatus,
+    /// Index into `StudyGuide::sections`, `None` before the first section.
+    pub section: Option<usize>,
+    /// How far the topic is nested under the ones above it, 0 for top level.
+    pub depth: usize,
+    /// Article to use instead of searching, from `Topic => Title`.
+    pub pinned_title: Option<String>,
+    /// Language edition to look the topic up in, from `[lang=fr]`.
+    pub language: Option<String>,
+    /// How many sentences of the summary to keep, from `{sentences=3}`.
+    pub sentences: Option<usize>,
+    /// The user's own notes, kept alongside the summary.
+    pub notes: Option<String>,
+    /// Line of the text or Markdown file, or paragraph of the Word document, the topic was read
+    /// from, so the summary can be filled in there.
+    pub origin: Option<usize>,
+}
+
+impl TopicEntry
+{
+    pub fn new(query: impl Into<String>) -> TopicEntry
+    {
+        TopicEntry {
+            query: query.into(),
+            enabled: true,
+            ..TopicEntry::default()
+        }
+    }
+
+    pub fn reset(&mut self)
+    {
+        self.candidates.clear();
+        self.suggestion = None;
+        self.chosen_title = None;
+        self.confidence = None;
+        self.summary = None;
+        self.url = None;
+        self.revision = None;
+        self.status = TopicStatus::Pending;
+    }
+
+    pub fn set_candidates(&mut self, candidates: Vec<String>)
+    {
+        self.status = if candidates.is_empty() { TopicStatus::NotFound } else { TopicStatus::Pending };
+        self.candidates = candidates;
+    }
+
+    pub fn set_summary(&mut self, title: String, summary: Summary)
+    {
+        let text = match self.sentences
+        {
+            Some(count) => first_sentences(&summary.text, count),
+            None => summary.text,
+        };
+        self.chosen_title = Some(title);
+        self.summary = Some(text);
+        self.url = Some(summary.url).filter(|url| !url.is_empty());
+        self.revision = summary.revision;
+        self.status = TopicStatus::Found;
+    }
+
+    /// Replaces the disambiguation page `title` among the candidates with the articles it lists,
+    /// leaving the topic for the user to choose one.
+    pub fn set_meanings(&mut self, title: &str, meanings: Vec<String>)
+    {
+        let at = self.candidates.iter().position(|candidate| candidate == title).unwrap_or(self.candidates.len());
+        self.candidates.retain(|candidate| candidate != title);
+        let meanings = meanings.into_iter()
+            .filter(|meaning| !self.candidates.contains(meaning))
+            .collect::<Vec<_>>();
+        let at = at.min(self.candidates.len());
+        self.candidates.splice(at..at, meanings);
+        self.chosen_title = None;
+        self.confidence = None;
+        self.summary = None;
+        self.url = None;
+        self.revision = None;
+        self.status = if self.candidates.is_empty() { TopicStatus::NotFound } else { TopicStatus::Ambiguous };
+    }
+
+    /// Searches for the suggested spelling from now on, returning the query it replaces.
+    pub fn accept_suggestion(&mut self) -> Option<String>
+    {
+        let suggestion = self.suggestion.take()?;
+        self.reset();
+        Some(std::mem::replace(&mut self.query, suggestion))
+    }
+
+    pub fn fail(&mut self, message: impl Into<String>)
+    {
+        self.status = TopicStatus::Failed(message.into());
+    }
+
+    pub fn is_found(&self) -> bool
+    {
+        self.status == TopicStatus::Found
+    }
+
+    /// Found, but the article was picked with little confidence and may be the wrong one.
+    pub fn needs_review(&self) -> bool
+    {
+        self.is_found() && self.confidence.is_some_and(|confidence| confidence < LOW_CONFIDENCE)
+    }
+
+    pub fn lookup(&self) -> LookupOptions
+    {
+        LookupOptions {
+            language: self.language.clone(),
+            ..LookupOptions::default()
+        }
+    }
+
+    /// The directives set on the topic's line, e.g. `=> American Civil War, lang=fr`.
+    pub fn directives(&self) -> Option<String>
+    {
+        let mut directives = Vec::new();
+        if let Some(title) = &self.pinned_title
+        {
+            directives.push(format!("=> {}", title));
+        }
+        if let Some(language) = &self.language
+        {
+            directives.push(format!("lang={}", language));
+        }
+        if let Some(sentences) = self.sentences
+        {
+            directives.push(format!("sentences={}", sentences));
+        }
+        (!directives.is_empty()).then(|| directives.join(", "))
+    }
+}
+
+/// The first `count` sentences of `text`, counting a line break as the end of one.
+fn first_sentences(text: &str, count: usize) -> String
+{
+    let mut end = 0;
+    let mut found = 0;
+    let chars = text.char_indices().collect::<Vec<_>>();
+    for (n, (i, c)) in chars.iter().enumerate()
+    {
+        let next = chars.get(n + 1).map(|(_, c)| *c);
+        let ends = match c
+        {
+            '\n' => true,
+            '.' => next.is_none_or(char::is_whitespace) && !is_abbreviation(&text[..*i], &text[i + 1..]),
+            '!' | '?' => next.is_none_or(char::is_whitespace),
+            _ => false,
+        };
+        if ends && !text[end..*i].trim().is_empty()
+        {
+            found += 1;
+            end = i + c.len_utf8();
+            if found == count
+            {
+                break;
+            }
+        }
+        else if ends
+        {
+            end = i + c.len_utf8();
+        }
+    }
+    if found < count
+    {
+        return text.to_string();
+    }
+    text[..end].trim_end().to_string()
+}
+
+/// Whether the word before a full stop is an abbreviation or an initial, like `Dr.`, `U.S.` or `J.`,
+/// rather than the end of a sentence. `after` is the text following the full stop.
+fn is_abbreviation(before: &str, after: &str) -> bool
+{
+    const TITLES: [&str; 10] = ["mr", "mrs", "ms", "dr", "st", "jr", "sr", "vs", "e.g", "i.e"];
+    // Only abbreviations before a number, like `No. 5` or `c. 1200`.
+    const NUMBERED: [&str; 3] = ["no", "c", "ca"];
+    let word = before.rsplit(char::is_whitespace).next().unwrap_or_default().trim_start_matches(['(', '"', '\'']);
+    let lower = word.to_lowercase();
+    let next = after.trim_start().chars().next();
+    let single = word.chars().count() == 1 && word.chars().all(char::is_uppercase);
+    // `U.S.` and `Ph.D.`, but not a number like `2.0`.
+    let dotted = word.contains('.') && word.split('.').all(|part| (1..=2).contains(&part.chars().count()) && part.chars().all(char::is_alphabetic));
+    single
+        || dotted
+        || TITLES.contains(&lower.as_str())
+        || (NUMBERED.contains(&lower.as_str()) && next.is_some_and(|c| c.is_ascii_digit()))
+        || (lower == "etc" && !next.is_some_and(char::is_uppercase))
+}
+
+/// A run of topics started by a header line, or by a blank line when `title` is `None`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Section
+{
+    pub title: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StudyGuide
+{
+    /// Usually the name of the input file.
+    pub title: Option<String>,
+    pub sections: Vec<Section>,
+    pub entries: Vec<TopicEntry>,
+    /// Where the summaries came from, set once they are fetched.
+    pub attribution: Option<Attribution>,
+    /// What the import couldn't make sense of, like a `[lang=f r]` directive, to tell the user.
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+impl StudyGuide
+{
+    pub fn from_topics(topics: Vec<String>) -> StudyGuide
+    {
+        StudyGuide {
+            title: None,
+            sections: Vec::new(),
+            entries: topics.into_iter().map(TopicEntry::new).collect(),
+            attribution: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn section_title(&self, entry: &TopicEntry) -> Option<&str>
+    {
+        entry.section.and_then(|i| self.sections.get(i)).and_then(|section| section.title.as_deref())
+    }
+
+    /// Whether any section has a header, which pushes topic headings down a level.
+    pub fn has_section_titles(&self) -> bool
+    {
+        self.sections.iter().any(|section| section.title.is_some())
+    }
+
+    /// Whether `i` is the first entry of its section.
+    pub fn starts_section(&self, i: usize) -> bool
+    {
+        match self.entries.get(i)
+        {
+            Some(entry) => entry.section.is_some() && (i == 0 || self.entries[i - 1].section != entry.section),
+            None => false,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.entries.is_empty()
+    }
+
+    pub fn clear_results(&mut self)
+    {
+        for entry in self.entries.iter_mut()
+        {
+            entry.reset();
+        }
+    }
+
+    /// Stores a fetched entry unless the topic list changed since it was requested.
+    pub fn apply(&mut self, i: usize, entry: TopicEntry)
+    {
+        if let Some(current) = self.entries.get_mut(i)
+        {
+            if current.query == entry.query
+            {
+                *current = entry;
+            }
+        }
+    }
+
+    pub fn cancel_pending(&mut self)
+    {
+        for entry in self.entries.iter_mut()
+        {
+            if entry.enabled && entry.status == TopicStatus::Pending
+            {
+                entry.status = TopicStatus::Cancelled;
+            }
+        }
+    }
+
+    pub fn has_results(&self) -> bool
+    {
+        self.entries.iter().any(|entry| entry.status != TopicStatus::Pending)
+    }
+
+    /// How many enabled topics ended up in each status, e.g. `5 ok, 1 not found, 2 error`.
+    pub fn status_summary(&self) -> String
+    {
+        let mut counts = Vec::<(&str, usize)>::new();
+        for entry in self.entries.iter().filter(|entry| entry.enabled)
+        {
+            match counts.iter_mut().find(|(label, _)| *label == entry.status.label())
+            {
+                Some((_, count)) => *count += 1,
+                None => counts.push((entry.status.label(), 1)),
+            }
+        }
+        let review = self.entries.iter().filter(|entry| entry.enabled && entry.needs_review()).count();
+        if review > 0
+        {
+            counts.push(("to review", review));
+        }
+        counts.iter().map(|(label, count)| format!("{} {}", count, label)).collect::<Vec<_>>().join(", ")
+    }
+
+    /// True once every enabled topic has either a summary or a final failure.
+    pub fn is_complete(&self) -> bool
+    {
+        self.entries.iter().any(|entry| entry.enabled)
+            && self.entries.iter().all(|entry| !entry.enabled || !matches!(entry.status, TopicStatus::Pending | TopicStatus::Cancelled))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn first_sentences_end_at_full_stops_and_line_breaks()
+    {
+        assert_eq!(first_sentences("One. Two! Three? Four.", 3), "One. Two! Three?");
+        assert_eq!(first_sentences("One\nTwo. Three.", 2), "One\nTwo.");
+        assert_eq!(first_sentences("Only one.", 3), "Only one.");
+        assert_eq!(first_sentences("Released as version 2.0. Next came 3.0.", 1), "Released as version 2.0.");
+        assert_eq!(first_sentences("She answered no. Then she left.", 1), "She answered no.");
+        assert_eq!(first_sentences("Pens, paper, etc. Then the exam.", 1), "Pens, paper, etc.");
+    }
+
+    #[test]
+    fn cancelled_topics_are_neither_done_nor_failed()
+    {
+        let mut guide = StudyGuide::from_topics(vec!["Mitosis".to_string(), "Meiosis".to_string()]);
+        guide.entries[0].status = TopicStatus::NotFound;
+        guide.cancel_pending();
+        assert_eq!(guide.entries[1].status, TopicStatus::Cancelled);
+        assert!(!guide.is_complete());
+        assert_eq!(guide.status_summary(), "1 not found, 1 cancelled");
+        assert!(guide.entries[1].status.can_retry());
+    }
+
+    #[test]
+    fn abbreviations_do_not_end_sentences()
+    {
+        assert_eq!(first_sentences("Dr. Martin Luther King Jr. was born in the U.S. in 1929. He died in 1968.", 1), "Dr. Martin Luther King Jr. was born in the U.S. in 1929.");
+        assert_eq!(first_sentences("J. R. R. Tolkien wrote it c. 1937. It sold well.", 1), "J. R. R. Tolkien wrote it c. 1937.");
+        assert_eq!(first_sentences("Symphony No. 5 is by Beethoven. It is in C minor.", 1), "Symphony No. 5 is by Beethoven.");
+        assert_eq!(first_sentences("Pens, paper, etc. are allowed. Phones are not.", 1), "Pens, paper, etc. are allowed.");
+        assert_eq!(first_sentences("Cities, e.g. Paris, grew. Villages shrank.", 1), "Cities, e.g. Paris, grew.");
+    }
+}
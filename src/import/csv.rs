@@ -0,0 +1,142 @@
+use crate::guide::StudyGuide;
+use crate::import::{Column, ImportError, ImportOptions, NOTES_HEADERS, TOPIC_HEADERS};
+use crate::parser::Builder;
+
+/// Reads a topic per row from the chosen column, or the first one, with notes from a second column.
+pub fn parse(content: &str, delimiter: char, options: &ImportOptions) -> Result<StudyGuide, ImportError>
+{
+    let records = records(content, delimiter);
+    let header = records.first().filter(|first| has_header(first, options));
+
+    let column = match &options.column
+    {
+        Some(column) => index(column, header)?,
+        None => header.and_then(|header| find(header, &TOPIC_HEADERS)).unwrap_or(0),
+    };
+    let notes = match &options.notes_column
+    {
+        Some(column) => Some(index(column, header)?),
+        None => header.and_then(|header| find(header, &NOTES_HEADERS)),
+    };
+
+    let mut builder = Builder::default();
+    for record in records.iter().skip(header.is_some() as usize)
+    {
+        // Cells can span lines, but a topic is looked up as one.
+        let Some(topic) = record.get(column)
+            .map(|cell| cell.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|cell| !cell.is_empty()) else {
+            continue;
+        };
+        let entry = builder.topic(0, &topic);
+        entry.notes = notes
+            .and_then(|notes| record.get(notes))
+            .map(|cell| cell.trim().to_string())
+            .filter(|cell| !cell.is_empty());
+    }
+    Ok(builder.finish())
+}
+
+/// Splits a table into rows of cells, following RFC 4180 quoting so cells can hold
+/// delimiters, quotes (`""`) and line breaks.
+pub fn records(content: &str, delimiter: char) -> Vec<Vec<String>>
+{
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = content.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next()
+    {
+        match c
+        {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            },
+            '"' if quoted => quoted = false,
+            '"' if cell.trim().is_empty() => {
+                cell.clear();
+                quoted = true;
+            },
+            c if c == delimiter && !quoted => record.push(std::mem::take(&mut cell)),
+            '\r' if !quoted => (),
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut cell));
+                if record.iter().any(|cell| !cell.trim().is_empty())
+                {
+                    records.push(std::mem::take(&mut record));
+                }
+                record.clear();
+            },
+            c => cell.push(c),
+        }
+    }
+    record.push(cell);
+    if record.iter().any(|cell| !cell.trim().is_empty())
+    {
+        records.push(record);
+    }
+    records
+}
+
+/// The first row is a header if it names a known column, or one picked by name.
+pub(crate) fn has_header(first: &[String], options: &ImportOptions) -> bool
+{
+    let by_name = [&options.column, &options.notes_column]
+        .into_iter()
+        .flatten()
+        .any(|column| matches!(column, Column::Name(_)));
+    by_name || find(first, &TOPIC_HEADERS).is_some() || find(first, &NOTES_HEADERS).is_some()
+}
+
+fn find(header: &[String], names: &[&str]) -> Option<usize>
+{
+    header.iter().position(|cell| names.contains(&cell.trim().to_lowercase().as_str()))
+}
+
+fn index(column: &Column, header: Option<&Vec<String>>) -> Result<usize, ImportError>
+{
+    match column
+    {
+        Column::Index(i) => Ok(*i),
+        Column::Name(name) => header
+            .and_then(|header| header.iter().position(|cell| cell.trim().eq_ignore_ascii_case(name)))
+            .ok_or_else(|| ImportError::MissingColumn(name.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn quoted_cells()
+    {
+        let records = records("\u{feff}Term,Notes\r\n\"Civil War, The\",\"said \"\"no\"\"\nand left\"\n\n,\n", ',');
+        assert_eq!(records, [
+            vec!["Term".to_string(), "Notes".to_string()],
+            vec!["Civil War, The".to_string(), "said \"no\"\nand left".to_string()],
+        ]);
+    }
+
+    #[test]
+    fn columns_from_the_header()
+    {
+        let guide = parse("Notes,Topic\nfirst,World War II\n,\"The Civil\nWar\"\n", ',', &ImportOptions::default()).unwrap();
+        let topics = guide.entries.iter().map(|entry| (entry.query.as_str(), entry.notes.as_deref())).collect::<Vec<_>>();
+        assert_eq!(topics, [("World War II", Some("first")), ("The Civil War", None)]);
+    }
+
+    #[test]
+    fn chosen_columns()
+    {
+        let options = ImportOptions { column: Some(Column::Index(1)), notes_column: None };
+        let guide = parse("1\tMitosis\n2\tMeiosis\n", '\t', &options).unwrap();
+        assert_eq!(guide.entries.iter().map(|entry| entry.query.as_str()).collect::<Vec<_>>(), ["Mitosis", "Meiosis"]);
+
+        let options = ImportOptions { column: Some(Column::Name("Word".to_string())), notes_column: None };
+        assert_eq!(parse("Term\nMitosis\n", ',', &options).unwrap_err(), ImportError::MissingColumn("Word".to_string()));
+    }
+}
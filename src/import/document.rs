@@ -0,0 +1,199 @@
+use std::collections::HashSet;
+use crate::guide::StudyGuide;
+use crate::import::markdown;
+use crate::parser::{self, Builder};
+
+/// Longest run of words still taken for a term rather than a sentence.
+const MAX_TERM_WORDS: usize = 6;
+
+/// A paragraph of a Word document or a line of a PDF.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Block
+{
+    pub text: String,
+    /// From the paragraph style, or guessed from a larger font.
+    pub heading: Option<usize>,
+    pub list: bool,
+    /// The bold stretches of `text`, in order.
+    pub bold: Vec<String>,
+    /// Index of the paragraph in the document, for filling it in later.
+    pub origin: Option<usize>,
+}
+
+struct Candidate
+{
+    term: String,
+    notes: Option<String>,
+    /// Unsure candidates are listed unchecked for the user to confirm.
+    sure: bool,
+}
+
+/// Picks the likely topics out of a document: `Term: definition` lines, terms in bold at the
+/// start of a paragraph, short list items and headings with nothing under them. Bold words
+/// inside running text are offered too, but left unchecked.
+pub(crate) fn candidates(title: Option<String>, blocks: &[Block]) -> StudyGuide
+{
+    let mut builder = Builder::default();
+    if let Some(title) = title
+    {
+        builder.title(title);
+    }
+    let mut seen = HashSet::new();
+    for (i, block) in blocks.iter().enumerate()
+    {
+        let text = collapse(&block.text);
+        if text.is_empty()
+        {
+            continue;
+        }
+        if let Some(level) = block.heading
+        {
+            let has_content = blocks[i + 1..].iter()
+                .take_while(|next| next.heading.is_none_or(|next| next > level))
+                .any(|next| next.heading.is_some() || !candidate(next).is_empty());
+            if has_content
+            {
+                builder.section(Some(text));
+            }
+            else if seen.insert(text.to_lowercase())
+            {
+                let entry = builder.topic(0, &text);
+                entry.enabled = false;
+                entry.origin = block.origin;
+            }
+            continue;
+        }
+
+        for candidate in candidate(block)
+        {
+            if seen.insert(candidate.term.to_lowercase())
+            {
+                let entry = builder.topic(0, &candidate.term);
+                entry.notes = candidate.notes;
+                entry.enabled = candidate.sure;
+                entry.origin = block.origin;
+            }
+        }
+    }
+    builder.finish()
+}
+
+fn candidate(block: &Block) -> Vec<Candidate>
+{
+    let text = collapse(&block.text);
+    let text = match markdown::list_item(&text)
+    {
+        Some(item) => item.to_string(),
+        None => text,
+    };
+    let bold = block.bold.iter()
+        .map(|bold| collapse(bold))
+        .filter(|bold| bold.chars().any(char::is_alphabetic))
+        .collect::<Vec<_>>();
+    let mut candidates = Vec::new();
+
+    // `Term: definition`, or a bold term the rest of the paragraph explains.
+    let leading = bold.first().filter(|bold| text.starts_with(bold.as_str()));
+    let split = match leading
+    {
+        Some(bold) => Some((bold.as_str(), &text[bold.len()..])),
+        None => text.split_once(':').filter(|(term, _)| !term.ends_with("http") && !term.ends_with("https")),
+    };
+    if let Some((term, rest)) = split.filter(|(term, _)| is_term(&clean(term)))
+    {
+        let notes = clean(rest.trim_start_matches([':', '-', '–', '—', ' ']));
+        candidates.push(Candidate {
+            term: clean(term),
+            notes: Some(notes).filter(|notes| !notes.is_empty() && !parser::is_blank(notes)),
+            sure: true,
+        });
+    }
+    else if block.list && is_term(&clean(&text))
+    {
+        candidates.push(Candidate { term: clean(&text), notes: None, sure: true });
+    }
+
+    for bold in bold.iter().skip(leading.is_some() as usize)
+    {
+        let term = clean(bold);
+        if is_term(&term)
+        {
+            candidates.push(Candidate { term, notes: None, sure: false });
+        }
+    }
+    candidates
+}
+
+fn is_term(text: &str) -> bool
+{
+    let words = text.split_whitespace().count();
+    (1..=MAX_TERM_WORDS).contains(&words) && text.chars().any(char::is_alphabetic)
+}
+
+/// Drops the punctuation a term is usually written with, like `Mitosis:` or `(Osmosis)`.
+fn clean(text: &str) -> String
+{
+    text.trim()
+        .trim_matches(['"', '“', '”', '(', ')', '*'])
+        .trim_end_matches([':', '.', ',', ';', '-', '–', '—'])
+        .trim()
+        .to_string()
+}
+
+fn collapse(text: &str) -> String
+{
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn block(text: &str) -> Block
+    {
+        Block { text: text.to_string(), ..Block::default() }
+    }
+
+    fn queries(guide: &StudyGuide) -> Vec<(&str, bool)>
+    {
+        guide.entries.iter().map(|entry| (entry.query.as_str(), entry.enabled)).collect()
+    }
+
+    #[test]
+    fn terms_and_definitions()
+    {
+        let guide = candidates(Some("Biology".to_string()), &[
+            block("Mitosis: ______"),
+            block("(Osmosis): the movement of water."),
+            block("See https://example.com for more."),
+            block("This sentence is far too long to be a term for anything."),
+        ]);
+        assert_eq!(guide.title.as_deref(), Some("Biology"));
+        assert_eq!(queries(&guide), [("Mitosis", true), ("Osmosis", true)]);
+        assert_eq!(guide.entries[0].notes, None);
+        assert_eq!(guide.entries[1].notes.as_deref(), Some("the movement of water"));
+    }
+
+    #[test]
+    fn bold_terms_and_list_items()
+    {
+        let leading = Block { bold: vec!["Meiosis".to_string(), "gametes".to_string()], ..block("Meiosis makes gametes.") };
+        let item = Block { list: true, origin: Some(7), ..block("Photosynthesis") };
+        let guide = candidates(None, &[leading, item, block("- meiosis: again")]);
+        assert_eq!(queries(&guide), [("Meiosis", true), ("gametes", false), ("Photosynthesis", true)]);
+        assert_eq!(guide.entries[0].notes.as_deref(), Some("makes gametes"));
+        assert_eq!(guide.entries[2].origin, Some(7));
+    }
+
+    #[test]
+    fn headings()
+    {
+        let heading = |text: &str, level: usize| Block { heading: Some(level), ..block(text) };
+        let guide = candidates(None, &[heading("Cells", 1), block("Mitosis: cell division"), heading("Genetics", 1), heading("Allele", 2)]);
+        assert_eq!(queries(&guide), [("Mitosis", true), ("Allele", false)]);
+        assert_eq!(guide.section_title(&guide.entries[0]), Some("Cells"));
+        assert_eq!(guide.section_title(&guide.entries[1]), Some("Genetics"));
+    }
+}
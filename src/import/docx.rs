@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+use crate::guide::StudyGuide;
+use crate::import::document::{self, Block};
+use crate::import::ImportError;
+use crate::xml::{tags, unescape};
+
+#[derive(Clone, Copy, Default)]
+struct Style
+{
+    heading: Option<usize>,
+    title: bool,
+    list: bool,
+}
+
+/// Reads the paragraphs of a Word document and picks the candidate topics out of them.
+pub fn parse(content: &[u8]) -> Result<StudyGuide, ImportError>
+{
+    let mut archive = ZipArchive::new(Cursor::new(content)).map_err(|e| ImportError::Document(e.to_string()))?;
+    let styles = part(&mut archive, "word/styles.xml").map(|xml| styles(&xml)).unwrap_or_default();
+    let document = part(&mut archive, "word/document.xml")
+        .ok_or_else(|| ImportError::Document("not a Word document".to_string()))?;
+
+    let mut title = None;
+    let mut blocks = Vec::new();
+    for (style, block) in paragraphs(&document, &styles)
+    {
+        if style.title && title.is_none()
+        {
+            title = Some(block.text.trim().to_string()).filter(|title| !title.is_empty());
+        }
+        else
+        {
+            blocks.push(block);
+        }
+    }
+    Ok(document::candidates(title, &blocks))
+}
+
+fn part(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Option<String>
+{
+    let mut xml = String::new();
+    archive.by_name(name).ok()?.read_to_string(&mut xml).ok()?;
+    Some(xml)
+}
+
+/// What the paragraph styles mean, by style id. Built-in styles keep English names like
+/// `heading 1` whatever language Word is in, while their ids get translated.
+fn styles(xml: &str) -> HashMap<String, Style>
+{
+    let mut styles = HashMap::new();
+    let mut current = None::<(String, Style)>;
+    for tag in tags(xml)
+    {
+        match tag.name
+        {
+            "w:style" if !tag.closing => {
+                current = tag.attribute("w:styleId").map(|id| (id, Style::default()));
+            },
+            "w:name" => {
+                if let (Some((_, style)), Some(name)) = (&mut current, tag.attribute("w:val"))
+                {
+                    let name = name.to_lowercase();
+                    style.heading = style.heading.or_else(|| name.strip_prefix("heading ").and_then(|level| level.parse().ok()));
+                    style.title |= name == "title";
+                    style.list |= name.starts_with("list");
+                }
+            },
+            "w:outlineLvl" => {
+                if let (Some((_, style)), Some(level)) = (&mut current, tag.attribute("w:val").and_then(|level| level.parse::<usize>().ok()))
+                {
+                    style.heading = style.heading.or(Some(level + 1));
+                }
+            },
+            "w:style" => {
+                if let Some((id, style)) = current.take()
+                {
+                    styles.insert(id, style);
+                }
+            },
+            _ => (),
+        }
+    }
+    styles
+}
+
+fn paragraphs(xml: &str, styles: &HashMap<String, Style>) -> Vec<(Style, Block)>
+{
+    let mut paragraphs = Vec::new();
+    let mut style = Style::default();
+    let mut block = Block::default();
+    let mut in_properties = false;
+    let mut in_text = false;
+    let mut bold = false;
+    let mut bold_text = String::new();
+    for tag in tags(xml)
+    {
+        match (tag.name, tag.closing)
+        {
+            ("w:p", false) if !tag.empty => {
+                style = Style::default();
+                block = Block::default();
+            },
+            ("w:p", true) => {
+                flush_bold(&mut block, &mut bold_text);
+                block.heading = style.heading;
+                block.list |= style.list;
+                block.origin = Some(paragraphs.len());
+                paragraphs.push((style, std::mem::take(&mut block)));
+            },
+            ("w:pPr", closing) => in_properties = !closing && !tag.empty,
+            ("w:pStyle", false) => {
+                if let Some(found) = tag.attribute("w:val").and_then(|id| styles.get(&id))
+                {
+                    style = *found;
+                }
+            },
+            ("w:numPr", false) => block.list = true,
+            ("w:r", false) => bold = false,
+            ("w:b", false) if !in_properties => {
+                bold = !matches!(tag.attribute("w:val").as_deref(), Some("0" | "false" | "off"));
+            },
+            ("w:t", false) => in_text = !tag.empty,
+            ("w:t", true) => in_text = false,
+            ("w:tab", false) | ("w:br", false) => block.text.push(' '),
+            ("#text", _) if in_text => {
+                let text = unescape(tag.text);
+                if bold
+                {
+                    bold_text.push_str(&text);
+                }
+                else
+                {
+                    flush_bold(&mut block, &mut bold_text);
+                }
+                block.text.push_str(&text);
+            },
+            _ => (),
+        }
+    }
+    paragraphs
+}
+
+fn flush_bold(block: &mut Block, bold_text: &mut String)
+{
+    if !bold_text.trim().is_empty()
+    {
+        block.bold.push(bold_text.trim().to_string());
+    }
+    bold_text.clear();
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    const STYLES: &str = r#"<w:styles>
+<w:style w:type="paragraph" w:styleId="Titel"><w:name w:val="Title"/></w:style>
+<w:style w:type="paragraph" w:styleId="berschrift1"><w:name w:val="heading 1"/></w:style>
+<w:style w:type="paragraph" w:styleId="Liste"><w:name w:val="List Paragraph"/></w:style>
+</w:styles>"#;
+
+    fn docx(body: &str) -> Vec<u8>
+    {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("word/styles.xml", SimpleFileOptions::default()).unwrap();
+        zip.write_all(STYLES.as_bytes()).unwrap();
+        zip.start_file("word/document.xml", SimpleFileOptions::default()).unwrap();
+        zip.write_all(format!("<w:document><w:body>{}</w:body></w:document>", body).as_bytes()).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn styles_and_bold_runs()
+    {
+        let guide = parse(&docx(concat!(
+            r#"<w:p><w:pPr><w:pStyle w:val="Titel"/></w:pPr><w:r><w:t>Biology</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:pStyle w:val="berschrift1"/></w:pPr><w:r><w:t>Cells</w:t></w:r></w:p>"#,
+            r#"<w:p><w:r><w:rPr><w:b/></w:rPr><w:t>Mitosis</w:t></w:r><w:r><w:t xml:space="preserve"> splits a cell &amp; its nucleus.</w:t></w:r></w:p>"#,
+            r#"<w:p/>"#,
+            r#"<w:p><w:pPr><w:pStyle w:val="Liste"/></w:pPr><w:r><w:t>Meiosis</w:t></w:r></w:p>"#,
+        ))).unwrap();
+        assert_eq!(guide.title.as_deref(), Some("Biology"));
+        let topics = guide.entries.iter().map(|entry| (entry.query.as_str(), entry.notes.as_deref(), entry.origin)).collect::<Vec<_>>();
+        assert_eq!(topics, [("Mitosis", Some("splits a cell & its nucleus"), Some(2)), ("Meiosis", None, Some(3))]);
+        assert_eq!(guide.section_title(&guide.entries[0]), Some("Cells"));
+    }
+
+    #[test]
+    fn not_a_word_document()
+    {
+        assert!(matches!(parse(b"World War II"), Err(ImportError::Document(_))));
+    }
+}
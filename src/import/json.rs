@@ -0,0 +1,147 @@
+use serde_json::{Map, Value};
+use crate::guide::StudyGuide;
+use crate::import::ImportError;
+use crate::parser::Builder;
+
+/// Reads topics from JSON in any of these shapes:
+/// - `["World War II", "The Civil War"]`,
+/// - `{"Unit 1": ["World War II"], "Unit 2": [...]}`, a section per key,
+/// - `{"title": "History", "topics": ...}` around either of those.
+///
+/// A topic can also be an object with `topic` and optionally `notes`, `article`, `lang`,
+/// `sentences` and nested `topics`.
+pub fn parse(content: &str) -> Result<StudyGuide, ImportError>
+{
+    let value = serde_json::from_str::<Value>(content).map_err(|e| ImportError::Json(e.to_string()))?;
+    let mut builder = Builder::default();
+    match &value
+    {
+        Value::Object(object) if object.contains_key("topics") => {
+            if let Some(title) = object.get("title").and_then(Value::as_str)
+            {
+                builder.title(title.to_string());
+            }
+            add(&mut builder, &object["topics"])?;
+        },
+        value => add(&mut builder, value)?,
+    }
+    Ok(builder.finish())
+}
+
+fn add(builder: &mut Builder, value: &Value) -> Result<(), ImportError>
+{
+    match value
+    {
+        Value::Array(topics) => topics.iter().try_for_each(|topic| add_topic(builder, topic, 0)),
+        Value::Object(sections) => {
+            for (title, topics) in sections
+            {
+                builder.section(Some(title.clone()));
+                match topics
+                {
+                    Value::Array(topics) => topics.iter().try_for_each(|topic| add_topic(builder, topic, 0))?,
+                    topic => add_topic(builder, topic, 0)?,
+                }
+            }
+            Ok(())
+        },
+        _ => Err(ImportError::Json("expected a list of topics or an object of sections".to_string())),
+    }
+}
+
+fn add_topic(builder: &mut Builder, value: &Value, depth: usize) -> Result<(), ImportError>
+{
+    match value
+    {
+        Value::String(topic) if !topic.trim().is_empty() => {
+            builder.topic(depth, topic.trim());
+            Ok(())
+        },
+        Value::Object(object) => {
+            let Some(topic) = ["topic", "query", "name", "term"].iter().find_map(|key| text(object, key)) else {
+                return Err(ImportError::Json(format!("topic without a name: {}", value)));
+            };
+            let entry = builder.topic(depth, &topic);
+            if let Some(notes) = text(object, "notes")
+            {
+                entry.notes = Some(notes);
+            }
+            if let Some(article) = text(object, "article")
+            {
+                entry.pinned_title = Some(article);
+            }
+            if let Some(language) = text(object, "lang").or_else(|| text(object, "language"))
+            {
+                entry.language = Some(language.to_lowercase());
+            }
+            if let Some(sentences) = object.get("sentences").and_then(Value::as_u64).filter(|sentences| *sentences > 0)
+            {
+                entry.sentences = Some(sentences as usize);
+            }
+            if let Some(Value::Array(topics)) = object.get("topics")
+            {
+                topics.iter().try_for_each(|topic| add_topic(builder, topic, depth + 1))?;
+            }
+            Ok(())
+        },
+        Value::String(_) | Value::Null => Ok(()),
+        value => Err(ImportError::Json(format!("unexpected topic {}", value))),
+    }
+}
+
+fn text(object: &Map<String, Value>, key: &str) -> Option<String>
+{
+    object.get(key)
+        .and_then(Value::as_str)
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn queries(guide: &StudyGuide) -> Vec<&str>
+    {
+        guide.entries.iter().map(|entry| entry.query.as_str()).collect()
+    }
+
+    #[test]
+    fn list_of_topics()
+    {
+        let guide = parse(r#"[" World War II ", "", null, "The Civil War"]"#).unwrap();
+        assert_eq!(queries(&guide), ["World War II", "The Civil War"]);
+    }
+
+    #[test]
+    fn sections_under_a_title()
+    {
+        let guide = parse(r#"{"title": "History", "topics": {"Unit 1": ["World War II"], "Unit 2": "The Civil War"}}"#).unwrap();
+        assert_eq!(guide.title.as_deref(), Some("History"));
+        assert_eq!(queries(&guide), ["World War II", "The Civil War"]);
+        assert_eq!(guide.section_title(&guide.entries[1]), Some("Unit 2"));
+    }
+
+    #[test]
+    fn topic_objects()
+    {
+        let guide = parse(r#"[{"topic": "Mercury", "article": "Mercury (planet)", "lang": "FR", "sentences": 2, "notes": "closest",
+            "topics": [{"name": "Orbit"}]}]"#).unwrap();
+        let entry = &guide.entries[0];
+        assert_eq!(entry.pinned_title.as_deref(), Some("Mercury (planet)"));
+        assert_eq!(entry.language.as_deref(), Some("fr"));
+        assert_eq!(entry.sentences, Some(2));
+        assert_eq!(entry.notes.as_deref(), Some("closest"));
+        assert_eq!((guide.entries[1].query.as_str(), guide.entries[1].depth), ("Orbit", 1));
+    }
+
+    #[test]
+    fn invalid_topics()
+    {
+        assert!(matches!(parse("[1]"), Err(ImportError::Json(_))));
+        assert!(matches!(parse(r#"[{"notes": "no name"}]"#), Err(ImportError::Json(_))));
+        assert!(matches!(parse("\"World War II\""), Err(ImportError::Json(_))));
+        assert!(matches!(parse("[\"World War II\""), Err(ImportError::Json(_))));
+    }
+}
@@ -0,0 +1,204 @@
+use crate::guide::StudyGuide;
+use crate::parser::{self, Builder};
+
+enum Line<'a>
+{
+    Heading(usize, &'a str),
+    Item(usize, &'a str),
+    /// Text that is neither, like a paragraph.
+    Other(usize, &'a str),
+}
+
+/// Reads topics from the list items of a Markdown document.
+///
+/// A heading with list items or smaller headings under it starts a section, one without becomes
+/// a topic itself, and a single `# Title` at the top names the guide. Other text is listed too,
+/// but left unchecked for the user to confirm.
+pub fn parse(content: &str) -> StudyGuide
+{
+    let mut lines = Vec::new();
+    let mut fenced = false;
+    for (i, line) in content.lines().enumerate()
+    {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~")
+        {
+            fenced = !fenced;
+        }
+        else if fenced
+        {
+            continue;
+        }
+        else if let Some((level, text)) = heading(line)
+        {
+            lines.push((i, Line::Heading(level, text)));
+        }
+        else if let Some(text) = list_item(line)
+        {
+            if !parser::is_blank(text)
+            {
+                lines.push((i, Line::Item(parser::indent_width(line), text)));
+            }
+        }
+        // Rules like `---`, table borders and answer blanks have nothing to look up.
+        else if trimmed.chars().any(char::is_alphanumeric) && !parser::is_blank(trimmed)
+        {
+            lines.push((i, Line::Other(parser::indent_width(line), trimmed.trim_end())));
+        }
+    }
+
+    let mut builder = Builder::default();
+    let titles = lines.iter().filter(|(_, line)| matches!(line, Line::Heading(1, _))).count();
+    if let (1, Some((_, Line::Heading(1, title)))) = (titles, lines.first())
+    {
+        builder.title(inline(title));
+        lines.remove(0);
+    }
+    for (i, (origin, line)) in lines.iter().enumerate()
+    {
+        match line
+        {
+            Line::Heading(level, text) => {
+                // Text under a heading is usually about it rather than a list of topics.
+                let next = lines[i + 1..].iter().find(|(_, line)| !matches!(line, Line::Other(..)));
+                let has_content = match next
+                {
+                    Some((_, Line::Item(..))) => true,
+                    Some((_, Line::Heading(next, _))) => next > level,
+                    Some((_, Line::Other(..))) | None => false,
+                };
+                if has_content
+                {
+                    builder.section(Some(inline(text)));
+                }
+                else
+                {
+                    builder.topic(0, &inline(parser::split_blank(text).0)).origin = Some(*origin);
+                }
+            },
+            Line::Item(indent, text) => {
+                builder.topic(*indent, &inline(parser::split_blank(text).0)).origin = Some(*origin);
+            },
+            Line::Other(indent, text) => {
+                let entry = builder.topic(*indent, &inline(parser::split_blank(text).0));
+                entry.origin = Some(*origin);
+                entry.enabled = false;
+            },
+        }
+    }
+    builder.finish()
+}
+
+/// The text of a `-`, `*`, `+` or `1.` list item, without the marker or a task box.
+pub fn list_item(line: &str) -> Option<&str>
+{
+    let trimmed = line.trim_start();
+    let rest = match trimmed.strip_prefix(['-', '*', '+'])
+    {
+        Some(rest) => rest,
+        None => {
+            let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+            if digits == 0 || digits > 9
+            {
+                return None;
+            }
+            trimmed[digits..].strip_prefix(['.', ')'])?
+        },
+    };
+    if !rest.starts_with([' ', '\t'])
+    {
+        return None;
+    }
+    let rest = rest.trim();
+    let rest = ["[ ]", "[x]", "[X]"].iter()
+        .find_map(|task| rest.strip_prefix(task))
+        .unwrap_or(rest)
+        .trim_start();
+    (!rest.is_empty()).then_some(rest)
+}
+
+fn heading(line: &str) -> Option<(usize, &str)>
+{
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t']))
+    {
+        return None;
+    }
+    let text = rest.trim().trim_end_matches('#').trim_end();
+    (!text.is_empty()).then_some((level, text))
+}
+
+/// Drops emphasis and code marks and keeps only the text of links.
+fn inline(text: &str) -> String
+{
+    let mut plain = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('[')
+    {
+        let link = rest[open + 1..].split_once("](").and_then(|(label, after)| {
+            after.find(')').map(|close| (label, &after[close + 1..]))
+        });
+        match link
+        {
+            Some((label, after)) if !label.contains(']') => {
+                plain.push_str(&rest[..open]);
+                plain.push_str(label);
+                rest = after;
+            },
+            _ => {
+                plain.push_str(&rest[..=open]);
+                rest = &rest[open + 1..];
+            },
+        }
+    }
+    plain.push_str(rest);
+    plain.replace("**", "").replace("__", "").replace('`', "").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn queries(guide: &StudyGuide) -> Vec<(&str, bool)>
+    {
+        guide.entries.iter().map(|entry| (entry.query.as_str(), entry.enabled)).collect()
+    }
+
+    #[test]
+    fn items_and_headings()
+    {
+        let guide = parse("# Biology\n\n## Cells\n- **Mitosis**: ______\n  1. [Meiosis](https://example.com)\n- [x] `Osmosis`\n\n## Photosynthesis\n");
+        assert_eq!(guide.title.as_deref(), Some("Biology"));
+        assert_eq!(queries(&guide), [("Mitosis", true), ("Meiosis", true), ("Osmosis", true), ("Photosynthesis", true)]);
+        assert_eq!(guide.section_title(&guide.entries[0]), Some("Cells"));
+        assert_eq!(guide.entries[1].depth, 1);
+        assert_eq!(guide.entries[1].origin, Some(4));
+    }
+
+    #[test]
+    fn other_text_is_left_unchecked()
+    {
+        let guide = parse("World War II\n- The Civil War\nThe Hundreds Year War\n---\n```\n- code\n```\n");
+        assert_eq!(queries(&guide), [("World War II", false), ("The Civil War", true), ("The Hundreds Year War", false)]);
+    }
+
+    #[test]
+    fn heading_over_text_is_a_topic()
+    {
+        let guide = parse("## Mitosis\nCell division.\n## Meiosis\n");
+        assert!(guide.sections.is_empty());
+        assert_eq!(queries(&guide), [("Mitosis", true), ("Cell division.", false), ("Meiosis", true)]);
+    }
+
+    #[test]
+    fn list_markers()
+    {
+        assert_eq!(list_item("  - [ ] Alpha "), Some("Alpha"));
+        assert_eq!(list_item("12) Beta"), Some("Beta"));
+        assert_eq!(list_item("1984 (novel)"), None);
+        assert_eq!(list_item("-dash"), None);
+        assert_eq!(list_item("- "), None);
+    }
+}
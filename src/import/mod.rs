@@ -0,0 +1,315 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::guide::StudyGuide;
+use crate::parser;
+
+pub mod csv;
+mod document;
+pub mod docx;
+pub mod json;
+pub mod markdown;
+pub mod pdf;
+
+/// Header names recognised as the topic column of a table.
+const TOPIC_HEADERS: [&str; 8] = ["topic", "topics", "term", "name", "title", "concept", "keyword", "subject"];
+/// Header names recognised as the notes column of a table.
+const NOTES_HEADERS: [&str; 6] = ["notes", "note", "description", "definition", "comment", "comments"];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InputFormat
+{
+    /// The topic file read by [`parser::parse`].
+    #[default]
+    Text,
+    Markdown,
+    Csv,
+    Tsv,
+    Json,
+    /// Candidate topics picked out of a Word document, for the user to confirm.
+    Docx,
+    /// Candidate topics picked out of a PDF's text, for the user to confirm.
+    Pdf,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImportError
+{
+    Json(String),
+    MissingColumn(String),
+    Document(String),
+}
+
+impl fmt::Display for ImportError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            ImportError::Json(message) => write!(f, "Invalid JSON: {}", message),
+            ImportError::MissingColumn(column) => write!(f, "No column \"{}\"", column),
+            ImportError::Document(message) => write!(f, "Could not read the document: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// A table column, by header name or by position.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Column
+{
+    Index(usize),
+    Name(String),
+}
+
+impl Column
+{
+    /// `3` is the third column, anything else a header name.
+    pub fn parse(text: &str) -> Column
+    {
+        match text.trim().parse::<usize>()
+        {
+            Ok(number) if number > 0 => Column::Index(number - 1),
+            _ => Column::Name(text.trim().to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Column
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Column::Index(i) => write!(f, "{}", i + 1),
+            Column::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Which columns of a CSV or TSV file to read, found from the header when left out.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImportOptions
+{
+    pub column: Option<Column>,
+    pub notes_column: Option<Column>,
+}
+
+impl InputFormat
+{
+    pub const ALL: [InputFormat; 7] = [
+        InputFormat::Text,
+        InputFormat::Markdown,
+        InputFormat::Csv,
+        InputFormat::Tsv,
+        InputFormat::Json,
+        InputFormat::Docx,
+        InputFormat::Pdf,
+    ];
+    /// Everything the open dialogs offer.
+    pub const EXTENSIONS: [&str; 9] = ["txt", "md", "markdown", "csv", "tsv", "tab", "json", "docx", "pdf"];
+
+    pub fn from_extension(extension: &str) -> Option<InputFormat>
+    {
+        match extension.to_lowercase().as_str()
+        {
+            "txt" | "text" => Some(InputFormat::Text),
+            "md" | "markdown" => Some(InputFormat::Markdown),
+            "csv" => Some(InputFormat::Csv),
+            "tsv" | "tab" => Some(InputFormat::Tsv),
+            "json" => Some(InputFormat::Json),
+            "docx" => Some(InputFormat::Docx),
+            "pdf" => Some(InputFormat::Pdf),
+            _ => None,
+        }
+    }
+
+    /// Goes by the extension, and by the content when there is none or it is a plain `.txt`.
+    pub fn detect(extension: Option<&str>, content: &[u8]) -> InputFormat
+    {
+        match extension.and_then(InputFormat::from_extension)
+        {
+            Some(InputFormat::Text) | None => InputFormat::sniff(content),
+            Some(format) => format,
+        }
+    }
+
+    fn sniff(content: &[u8]) -> InputFormat
+    {
+        if content.starts_with(b"%PDF")
+        {
+            return InputFormat::Pdf;
+        }
+        // Zip archives, and the only one read is a Word document.
+        if content.starts_with(b"PK\x03\x04")
+        {
+            return InputFormat::Docx;
+        }
+
+        let content = String::from_utf8_lossy(content);
+        let text = content.trim_start();
+        if (text.starts_with('[') || text.starts_with('{')) && serde_json::from_str::<Value>(text).is_ok()
+        {
+            return InputFormat::Json;
+        }
+
+        let lines = content.lines()
+            .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .collect::<Vec<_>>();
+        if lines.is_empty()
+        {
+            return InputFormat::Text;
+        }
+        // Tabs used for indenting sub-topics don't make a table.
+        if lines.iter().all(|line| line.trim_start().contains('\t'))
+        {
+            return InputFormat::Tsv;
+        }
+        // Only a file that is nothing but a list reads the same as Markdown, anything else is a topic list.
+        let items = lines.iter().filter(|line| markdown::list_item(line).is_some()).count();
+        if items > 0 && lines.iter().all(|line| markdown::list_item(line).is_some() || parser::is_blank(line))
+        {
+            return InputFormat::Markdown;
+        }
+        let header = csv::records(lines[0], ',').into_iter().next().unwrap_or_default();
+        if header.len() > 1 && header.iter().any(|cell| TOPIC_HEADERS.contains(&cell.trim().to_lowercase().as_str()))
+        {
+            return InputFormat::Csv;
+        }
+        InputFormat::Text
+    }
+
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            InputFormat::Text => "Topic list",
+            InputFormat::Markdown => "Markdown",
+            InputFormat::Csv => "CSV",
+            InputFormat::Tsv => "TSV",
+            InputFormat::Json => "JSON",
+            InputFormat::Docx => "Word document",
+            InputFormat::Pdf => "PDF",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str
+    {
+        match self
+        {
+            InputFormat::Text => "txt",
+            InputFormat::Markdown => "md",
+            InputFormat::Csv => "csv",
+            InputFormat::Tsv => "tsv",
+            InputFormat::Json => "json",
+            InputFormat::Docx => "docx",
+            InputFormat::Pdf => "pdf",
+        }
+    }
+
+    /// Whether the topics are guessed from a document and need confirming.
+    pub fn is_document(&self) -> bool
+    {
+        matches!(self, InputFormat::Docx | InputFormat::Pdf)
+    }
+
+    /// Whether the topics come from a column that [`ImportOptions`] can choose.
+    pub fn is_table(&self) -> bool
+    {
+        matches!(self, InputFormat::Csv | InputFormat::Tsv)
+    }
+
+    pub fn import(&self, content: &[u8], options: &ImportOptions) -> Result<StudyGuide, ImportError>
+    {
+        let text = || String::from_utf8_lossy(content);
+        match self
+        {
+            InputFormat::Text => Ok(parser::parse(&text())),
+            InputFormat::Markdown => Ok(markdown::parse(&text())),
+            InputFormat::Csv => csv::parse(&text(), ',', options),
+            InputFormat::Tsv => csv::parse(&text(), '\t', options),
+            InputFormat::Json => json::parse(&text()),
+            InputFormat::Docx => docx::parse(content),
+            InputFormat::Pdf => pdf::parse(content),
+        }
+    }
+
+    /// Names for the columns of a table, from its header or numbered when it has none.
+    pub fn columns(&self, content: &[u8]) -> Vec<String>
+    {
+        let delimiter = match self
+        {
+            InputFormat::Csv => ',',
+            InputFormat::Tsv => '\t',
+            _ => return Vec::new(),
+        };
+        let records = csv::records(&String::from_utf8_lossy(content), delimiter);
+        let Some(first) = records.first() else {
+            return Vec::new();
+        };
+        if csv::has_header(first, &ImportOptions::default())
+        {
+            first.iter().map(|cell| cell.trim().to_string()).collect()
+        }
+        else
+        {
+            (1..=first.len()).map(|i| format!("Column {}", i)).collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn detect_by_extension()
+    {
+        assert_eq!(InputFormat::detect(Some("MD"), b"World War II"), InputFormat::Markdown);
+        assert_eq!(InputFormat::detect(Some("csv"), b"World War II"), InputFormat::Csv);
+        assert_eq!(InputFormat::detect(Some("docx"), b""), InputFormat::Docx);
+    }
+
+    #[test]
+    fn sniff_content()
+    {
+        assert_eq!(InputFormat::detect(None, b"%PDF-1.7\n"), InputFormat::Pdf);
+        assert_eq!(InputFormat::detect(None, b"PK\x03\x04rest"), InputFormat::Docx);
+        assert_eq!(InputFormat::detect(Some("txt"), b" [\"Alpha\", \"Beta\"]"), InputFormat::Json);
+        assert_eq!(InputFormat::detect(None, b"topic\tnotes\nAlpha\tfirst\n"), InputFormat::Tsv);
+        assert_eq!(InputFormat::detect(None, b"Term,Notes\nAlpha,first\n"), InputFormat::Csv);
+        assert_eq!(InputFormat::detect(Some("txt"), b"World War II\n\tPearl Harbor\n"), InputFormat::Text);
+        assert_eq!(InputFormat::detect(Some("txt"), b""), InputFormat::Text);
+    }
+
+    #[test]
+    fn sniff_markdown_only_for_whole_lists()
+    {
+        assert_eq!(InputFormat::detect(Some("txt"), b"# my course\n- World War II\n- Civil War\n______\n"), InputFormat::Markdown);
+        assert_eq!(InputFormat::detect(Some("txt"), b"World War II\n- The Civil War\nThe Hundreds Year War"), InputFormat::Text);
+        assert_eq!(InputFormat::detect(Some("txt"), b"# my course\n1. World War II\n2. Civil War\nNapoleon"), InputFormat::Text);
+    }
+
+    #[test]
+    fn text_keeps_every_line()
+    {
+        let content = b"World War II\n- The Civil War\nThe Hundreds Year War";
+        let format = InputFormat::detect(Some("txt"), content);
+        let guide = format.import(content, &ImportOptions::default()).unwrap();
+        assert_eq!(guide.entries.len(), 3);
+    }
+
+    #[test]
+    fn column_names()
+    {
+        assert_eq!(Column::parse(" 2 "), Column::Index(1));
+        assert_eq!(Column::parse("0"), Column::Name("0".to_string()));
+        assert_eq!(Column::parse("Term"), Column::Name("Term".to_string()));
+        assert_eq!(InputFormat::Csv.columns(b"Term,Notes\nAlpha,first\n"), ["Term", "Notes"]);
+        assert_eq!(InputFormat::Csv.columns(b"Alpha,first\n"), ["Column 1", "Column 2"]);
+        assert!(InputFormat::Text.columns(b"Alpha").is_empty());
+    }
+}
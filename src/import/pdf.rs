@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use lopdf::content::Content;
+use lopdf::{Document, Encoding, Object, ObjectId};
+use crate::guide::StudyGuide;
+use crate::import::document::{self, Block};
+use crate::import::{markdown, ImportError};
+
+const BULLETS: [char; 8] = ['•', '◦', '▪', '▫', '‣', '●', '○', '■'];
+
+struct Span
+{
+    text: String,
+    size: f32,
+    bold: bool,
+}
+
+#[derive(Default)]
+struct Line
+{
+    spans: Vec<Span>,
+}
+
+impl Line
+{
+    fn text(&self) -> String
+    {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    fn size(&self) -> f32
+    {
+        self.spans.iter()
+            .filter(|span| !span.text.trim().is_empty())
+            .map(|span| span.size)
+            .fold(0.0, f32::max)
+    }
+}
+
+/// Reads the lines of a PDF with a text layer and picks the candidate topics out of them.
+/// Larger lines are taken as headings, and bold from the font names.
+pub fn parse(content: &[u8]) -> Result<StudyGuide, ImportError>
+{
+    let document = Document::load_mem(content).map_err(|e| ImportError::Document(e.to_string()))?;
+    let lines = document.get_pages()
+        .into_values()
+        .flat_map(|page| page_lines(&document, page))
+        .filter(|line| !line.text().trim().is_empty())
+        .collect::<Vec<_>>();
+    if lines.is_empty()
+    {
+        return Err(ImportError::Document("the PDF has no text, it may be scanned".to_string()));
+    }
+    Ok(document::candidates(None, &blocks(&lines)))
+}
+
+fn page_lines(document: &Document, page: ObjectId) -> Vec<Line>
+{
+    let fonts = document.get_page_fonts(page)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(name, font)| {
+            let encoding = font.get_font_encoding(document).ok()?;
+            let bold = font.get(b"BaseFont").and_then(Object::as_name_str).is_ok_and(is_bold);
+            Some((name, (encoding, bold)))
+        })
+        .collect::<HashMap<Vec<u8>, (Encoding, bool)>>();
+    let Ok(content) = document.get_page_content(page).and_then(|data| Content::decode(&data)) else {
+        return Vec::new();
+    };
+
+    let mut lines = vec![Line::default()];
+    let mut font = None;
+    let mut size = 0.0;
+    let mut scale = 1.0;
+    // Where the text line starts, and the height of the last text shown.
+    let mut line_y = 0.0;
+    let mut shown_y = None::<f32>;
+    let mut space = false;
+    for operation in content.operations
+    {
+        let operands = &operation.operands;
+        let number = |i: usize| operands.get(i).and_then(|operand| operand.as_float().ok());
+        let mut text = None;
+        match operation.operator.as_str()
+        {
+            "BT" => {
+                line_y = 0.0;
+                scale = 1.0;
+            },
+            "Tf" => {
+                font = operands.first().and_then(|name| name.as_name().ok()).and_then(|name| fonts.get(name));
+                size = number(1).unwrap_or(size);
+            },
+            "Tm" => {
+                scale = number(3).map(f32::abs).filter(|scale| *scale > 0.0).unwrap_or(1.0);
+                line_y = number(5).unwrap_or(line_y);
+                space = true;
+            },
+            "Td" | "TD" => {
+                line_y += number(1).unwrap_or_default() * scale;
+                space = true;
+            },
+            "T*" => line_y -= size * scale,
+            "'" | "\"" => {
+                line_y -= size * scale;
+                text = operands.last();
+            },
+            "Tj" | "TJ" => text = operands.first(),
+            _ => (),
+        }
+
+        let Some((encoding, bold)) = font else { continue };
+        let Some(text) = text.map(|text| decode(encoding, text)).filter(|text| !text.is_empty()) else {
+            continue;
+        };
+        let height = size * scale;
+        match shown_y
+        {
+            Some(y) if (y - line_y).abs() > height.max(1.0) * 0.5 => lines.push(Line::default()),
+            Some(_) if space => {
+                if let Some(last) = lines.last_mut().and_then(|line| line.spans.last_mut())
+                {
+                    if !last.text.ends_with(' ') && !text.starts_with(' ')
+                    {
+                        last.text.push(' ');
+                    }
+                }
+            },
+            _ => (),
+        }
+        shown_y = Some(line_y);
+        space = false;
+        if let Some(line) = lines.last_mut()
+        {
+            line.spans.push(Span { text, size: height, bold: *bold });
+        }
+    }
+    lines
+}
+
+/// Text of a `Tj` string or `TJ` array, with wide gaps in the array read as spaces.
+fn decode(encoding: &Encoding, operand: &Object) -> String
+{
+    match operand
+    {
+        Object::String(bytes, _) => Document::decode_text(encoding, bytes).unwrap_or_default(),
+        Object::Array(parts) => parts.iter()
+            .map(|part| match part
+            {
+                Object::Integer(gap) if *gap < -200 => " ".to_string(),
+                Object::Real(gap) if *gap < -200.0 => " ".to_string(),
+                part => decode(encoding, part),
+            })
+            .collect(),
+        _ => String::new(),
+    }
+}
+
+fn is_bold(font: &str) -> bool
+{
+    let font = font.to_lowercase();
+    ["bold", "black", "heavy", "semibold", "demi"].iter().any(|weight| font.contains(weight))
+}
+
+fn blocks(lines: &[Line]) -> Vec<Block>
+{
+    // The size most of the text is set in, anything clearly larger is a heading.
+    let mut sizes = HashMap::<u32, usize>::new();
+    for line in lines
+    {
+        *sizes.entry(line.size().round() as u32).or_default() += line.text().len();
+    }
+    let body = sizes.iter().max_by_key(|(_, count)| **count).map(|(size, _)| *size).unwrap_or_default();
+    let mut heading_sizes = sizes.keys().copied().filter(|size| *size as f32 >= body as f32 * 1.15).collect::<Vec<_>>();
+    heading_sizes.sort_unstable_by(|a, b| b.cmp(a));
+
+    lines.iter()
+        .map(|line| {
+            let mut text = line.text().trim().to_string();
+            if let Some(item) = text.strip_prefix(BULLETS)
+            {
+                text = format!("- {}", item.trim_start());
+            }
+            let size = line.size().round() as u32;
+            let short = text.split_whitespace().count() <= 12;
+            let mut bold = Vec::<String>::new();
+            let mut previous = false;
+            for span in &line.spans
+            {
+                match (span.bold, previous, bold.last_mut())
+                {
+                    (true, true, Some(last)) => last.push_str(&span.text),
+                    (true, _, _) => bold.push(span.text.clone()),
+                    _ => (),
+                }
+                previous = span.bold;
+            }
+            Block {
+                heading: heading_sizes.iter().position(|heading| *heading == size).filter(|_| short).map(|level| level + 1),
+                list: markdown::list_item(&text).is_some(),
+                text,
+                bold,
+                origin: None,
+            }
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::guide::TopicEntry;
+    use crate::writer::{pdf, WriteOptions};
+
+    #[test]
+    fn reads_the_text_layer()
+    {
+        let mut entry = TopicEntry::new("Mitosis");
+        entry.summary = Some("Mitosis: the division of a cell nucleus.".to_string());
+        let guide = StudyGuide { entries: vec![entry], ..StudyGuide::default() };
+        let options = WriteOptions { table_of_contents: false, footer: false, ..WriteOptions::default() };
+        let mut content = Vec::new();
+        pdf::write(&guide, &options, &mut content).unwrap();
+
+        let imported = parse(&content).unwrap();
+        let mitosis = imported.entries.iter().find(|entry| entry.query == "Mitosis").unwrap();
+        assert!(mitosis.enabled);
+        assert_eq!(mitosis.notes.as_deref(), Some("the division of a cell nucleus"));
+    }
+
+    #[test]
+    fn not_a_pdf()
+    {
+        assert!(matches!(parse(b"World War II"), Err(ImportError::Document(_))));
+    }
+}
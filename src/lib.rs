@@ -0,0 +1,14 @@
+pub mod cache;
+pub mod error;
+pub mod fetch;
+pub mod fill;
+pub mod guide;
+pub mod import;
+pub mod parser;
+pub mod preview;
+pub mod project;
+pub mod rank;
+pub mod source;
+pub mod spelling;
+pub mod writer;
+mod xml;
@@ -1,19 +1,63 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
+mod notifications;
 #[cfg(not(target_arch = "wasm32"))]
 mod window;
+mod workspace;
 
 #[cfg(target_arch = "wasm32")]
 mod wasm_window;
 
+use study_guide_filler::cache::SummaryCache;
+use study_guide_filler::source::wikipedia::Wikipedia;
+
 #[cfg(not(target_arch = "wasm32"))]
-fn main() -> eframe::Result<()>
+fn main() -> std::process::ExitCode
 {
-    eframe::run_native(
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    if !args.is_empty()
+    {
+        #[cfg(all(windows, not(debug_assertions)))]
+        attach_console();
+        return cli::run(args);
+    }
+
+    let result = eframe::run_native(
         "Filler",
         eframe::NativeOptions::default(),
-        Box::new(|_cc| Ok( Box::new(window::Window::new()) ))
-    )
+        Box::new(|cc| Ok( Box::new(window::Window::new(Wikipedia::default(), SummaryCache::open_default(), cc.storage)) ))
+    );
+    match result
+    {
+        Ok(_) => std::process::ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::ExitCode::FAILURE
+        },
+    }
+}
+
+/// Release builds on Windows are GUI programs without a console of their own, so the command
+/// line report is written to the console they were started from.
+#[cfg(all(windows, not(debug_assertions)))]
+fn attach_console()
+{
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+    #[link(name = "kernel32")]
+    extern "system"
+    {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+
+    // Fails when there is no parent console, e.g. when started from Explorer, and then there is
+    // nowhere to write to anyway.
+    unsafe
+    {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -37,7 +81,7 @@ fn main()
             .start(
                 canvas,
                 eframe::WebOptions::default(),
-                Box::new( |_cc| Ok(Box::new( wasm_window::WasmWindow::new()) )),
+                Box::new( |cc| Ok(Box::new( wasm_window::WasmWindow::new(Wikipedia::default(), SummaryCache::open_default(), cc.storage)) )),
             )
             .await;
 
@@ -0,0 +1,142 @@
+use std::fmt::Display;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+/// How long a notice that is not an error stays up, in seconds.
+const NOTICE_SECONDS: f64 = 5.0;
+
+pub struct Notification
+{
+    text: String,
+    error: bool,
+    /// When it was first drawn, in `egui` input time.
+    shown_at: Option<f64>,
+    /// The project as written, when this tells of it being saved.
+    session: Option<String>,
+}
+
+impl Notification
+{
+    pub fn error(error: impl Display) -> Notification
+    {
+        Notification { text: error.to_string(), error: true, shown_at: None, session: None }
+    }
+
+    pub fn notice(text: impl Into<String>) -> Notification
+    {
+        Notification { text: text.into(), error: false, shown_at: None, session: None }
+    }
+
+    /// Tells how writing the file `name` went.
+    pub fn saved<E: Display>(name: impl Display, result: Result<(), E>) -> Notification
+    {
+        match result
+        {
+            Ok(()) => Notification::notice(format!("Saved {}", name)),
+            Err(e) => Notification::error(format!("Could not save {}: {}", name, e)),
+        }
+    }
+
+    /// Tells how saving the project `session` to the file `name` went, passing it on to
+    /// [`Notifications::take_saved_session`] if it was saved.
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn saved_project<E: Display>(name: impl Display, result: Result<(), E>, session: String) -> Notification
+    {
+        let saved = result.is_ok();
+        let mut notification = Notification::saved(name, result);
+        notification.session = saved.then_some(session);
+        notification
+    }
+}
+
+/// Errors and notices shown above the topics. Errors stay until they are dismissed,
+/// notices go away by themselves.
+pub struct Notifications
+{
+    list: Vec<Notification>,
+    /// For tasks that finish after the frame that started them, like saving on the web.
+    channel: (Sender<Notification>, Receiver<Notification>),
+    /// The project last saved by one of those tasks.
+    saved_session: Option<String>,
+}
+
+impl Default for Notifications
+{
+    fn default() -> Notifications
+    {
+        Notifications {
+            list: Vec::new(),
+            channel: channel(),
+            saved_session: None,
+        }
+    }
+}
+
+impl Notifications
+{
+    pub fn push(&mut self, notification: Notification)
+    {
+        self.list.push(notification);
+    }
+
+    pub fn error(&mut self, error: impl Display)
+    {
+        self.push(Notification::error(error));
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn sender(&self) -> Sender<Notification>
+    {
+        self.channel.0.clone()
+    }
+
+    /// The project as it was written, once a save sent through [`Notifications::sender`] is done.
+    pub fn take_saved_session(&mut self) -> Option<String>
+    {
+        self.receive();
+        self.saved_session.take()
+    }
+
+    fn receive(&mut self)
+    {
+        while let Ok(mut notification) = self.channel.1.try_recv()
+        {
+            if let Some(session) = notification.session.take()
+            {
+                self.saved_session = Some(session);
+            }
+            self.list.push(notification);
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui)
+    {
+        self.receive();
+        let time = ui.input(|input| input.time);
+        self.list.retain(|notification| notification.error || notification.shown_at.is_none_or(|shown_at| time - shown_at < NOTICE_SECONDS));
+
+        let mut dismissed = None;
+        for (i, notification) in self.list.iter_mut().enumerate()
+        {
+            let shown_at = *notification.shown_at.get_or_insert(time);
+            ui.horizontal(|ui| {
+                if notification.error
+                {
+                    ui.colored_label(ui.visuals().error_fg_color, &notification.text);
+                }
+                else
+                {
+                    ui.label(&notification.text);
+                    ui.ctx().request_repaint_after_secs((shown_at + NOTICE_SECONDS - time) as f32);
+                }
+                if ui.small_button("✖").on_hover_text("Dismiss").clicked()
+                {
+                    dismissed = Some(i);
+                }
+            });
+        }
+        if let Some(i) = dismissed
+        {
+            self.list.remove(i);
+        }
+    }
+}
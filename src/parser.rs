@@ -0,0 +1,403 @@
+use crate::guide::{Section, StudyGuide, TopicEntry};
+
+const TAB_WIDTH: usize = 4;
+const SECTION_WORDS: [&str; 9] = ["unit", "chapter", "section", "part", "module", "week", "lesson", "day", "period"];
+pub(crate) const BLANKS: [char; 3] = ['_', '.', '…'];
+
+/// The plain list of topics, without sections or nesting.
+pub fn parse_topics(content: &str) -> Vec<String>
+{
+    parse(content).entries.into_iter().map(|entry| entry.query).collect()
+}
+
+/// Reads a topic file where
+/// - `#` starts a comment, on its own line or after a topic,
+/// - lines like `Unit 3: WWII` or `Causes:` followed by topics start a section, as does `Week 2`
+///   with indented lines under it,
+/// - a blank line between topics starts an untitled section,
+/// - indented lines are sub-topics of the less indented line above them,
+/// - `Topic => Title` pins the article, and trailing `[lang=fr]` or `{sentences=3}`
+///   groups set the language and summary length of that topic,
+/// - blanks like `Topic: ______` or a line of `____` under a topic are left for its answer.
+pub fn parse(content: &str) -> StudyGuide
+{
+    let mut builder = Builder::default();
+    let lines = content.lines().map(strip_comment).collect::<Vec<_>>();
+    for (i, line) in lines.iter().copied().enumerate()
+    {
+        let text = line.trim();
+        if text.is_empty()
+        {
+            builder.blank();
+        }
+        else if is_blank(text)
+        {
+            continue;
+        }
+        else if let Some(title) = header(&lines, i)
+        {
+            builder.section(Some(title));
+        }
+        else
+        {
+            // `Mitosis:` over a blank line is a topic, not a header.
+            let text = text.strip_suffix(':').map(str::trim_end).filter(|text| !text.is_empty()).unwrap_or(text);
+            builder.topic(indent_width(line), text).origin = Some(i);
+        }
+    }
+    builder.finish()
+}
+
+/// Builds a guide a line at a time, for the topic file and the importers.
+#[derive(Default)]
+pub(crate) struct Builder
+{
+    guide: StudyGuide,
+    indents: Vec<usize>,
+    blank: bool,
+}
+
+impl Builder
+{
+    pub(crate) fn section(&mut self, title: Option<String>)
+    {
+        self.guide.sections.push(Section { title });
+        self.indents.clear();
+        self.blank = false;
+    }
+
+    /// A blank line, which starts an untitled section before the next topic.
+    pub(crate) fn blank(&mut self)
+    {
+        self.blank = true;
+    }
+
+    /// Adds a topic nested under the last one that was less indented, returning it for further changes.
+    pub(crate) fn topic(&mut self, indent: usize, text: &str) -> &mut TopicEntry
+    {
+        // A blank line right after a header doesn't split anything off.
+        if self.blank && self.guide.entries.last().is_some_and(|entry| entry.section == current(&self.guide))
+        {
+            self.section(None);
+        }
+        self.blank = false;
+
+        while self.indents.last().is_some_and(|last| *last >= indent)
+        {
+            self.indents.pop();
+        }
+        let mut entry = TopicEntry::new(strip_blank(text));
+        strip_directives(&mut entry, &mut self.guide.warnings);
+        entry.section = current(&self.guide);
+        entry.depth = self.indents.len();
+        self.indents.push(indent);
+        self.guide.entries.push(entry);
+        let last = self.guide.entries.len() - 1;
+        &mut self.guide.entries[last]
+    }
+
+    pub(crate) fn title(&mut self, title: String)
+    {
+        self.guide.title = Some(title);
+    }
+
+    pub(crate) fn finish(self) -> StudyGuide
+    {
+        self.guide
+    }
+}
+
+/// Whether the text is only a blank left to write an answer in, like `______` or `.....`.
+pub(crate) fn is_blank(text: &str) -> bool
+{
+    let text = text.trim();
+    text.chars().all(|c| BLANKS.contains(&c) || c.is_whitespace()) && text.chars().filter(|c| BLANKS.contains(c)).count() >= 3
+}
+
+/// Splits `Topic: ______` into the topic and the blank after it, if there is one.
+pub(crate) fn split_blank(text: &str) -> (&str, Option<&str>)
+{
+    let trimmed = text.trim_end();
+    let start = trimmed.len() - trimmed.chars()
+        .rev()
+        .take_while(|c| BLANKS.contains(c) || c.is_whitespace())
+        .map(char::len_utf8)
+        .sum::<usize>();
+    let blank = &trimmed[start..];
+    // Dots right after a word, as in `And Then There Were None...`, are part of the title.
+    let trailing_dots = !blank.starts_with(char::is_whitespace) && !blank.contains('_') && trimmed[..start].ends_with(char::is_alphanumeric);
+    if start == 0 || trailing_dots || !is_blank(blank)
+    {
+        return (text, None);
+    }
+    let topic = trimmed[..start].trim_end().trim_end_matches([':', '-', '–', '—', '=']).trim_end();
+    (topic, Some(blank.trim()))
+}
+
+fn strip_blank(text: &str) -> &str
+{
+    split_blank(text).0
+}
+
+fn current(guide: &StudyGuide) -> Option<usize>
+{
+    guide.sections.len().checked_sub(1)
+}
+
+fn strip_comment(line: &str) -> &str
+{
+    if line.trim_start().starts_with('#')
+    {
+        return "";
+    }
+    // After a topic only `  # note` or `\t# note` is a comment, so `C#` and `Symphony #5` keep theirs.
+    let comment = line.match_indices('#').map(|(i, _)| i).find(|&i| {
+        let before = &line[..i];
+        let spaced = before.ends_with("  ") || before.ends_with('\t');
+        spaced && line[i + 1..].chars().next().is_none_or(char::is_whitespace)
+    });
+    match comment
+    {
+        Some(i) => &line[..i],
+        None => line,
+    }
+}
+
+/// Moves trailing `[key=value]` / `{key=value}` groups and a `=> Title` out of the query,
+/// adding a warning for each value that is ignored.
+fn strip_directives(entry: &mut TopicEntry, warnings: &mut Vec<String>)
+{
+    let line = entry.query.clone();
+    let mut query = line.as_str();
+    loop
+    {
+        let close = match query.chars().last()
+        {
+            Some(']') => '[',
+            Some('}') => '{',
+            _ => break,
+        };
+        let Some(open) = query.rfind(close) else { break };
+        if !apply_directives(entry, &query[open + 1..query.len() - 1], &line, warnings)
+        {
+            break;
+        }
+        query = query[..open].trim_end();
+    }
+
+    let (query, pinned) = match query.split_once("=>")
+    {
+        Some((query, title)) => (query.trim(), Some(title.trim())),
+        None => (query, None),
+    };
+    if let Some(title) = pinned.filter(|title| !title.is_empty())
+    {
+        entry.pinned_title = Some(title.to_string());
+    }
+    // A line that is only directives keeps them as its query rather than becoming empty.
+    if !query.is_empty()
+    {
+        entry.query = query.to_string();
+    }
+}
+
+/// Applies a group like `lang=fr, sentences=3` found on `line`, returning false if it isn't one,
+/// so topics such as `Sonata [music]` are left alone.
+fn apply_directives(entry: &mut TopicEntry, group: &str, line: &str, warnings: &mut Vec<String>) -> bool
+{
+    // `lang=fr sentences=3` is two pairs, but `title=American Civil War` is one.
+    let pairs = group.split([',', ';'])
+        .flat_map(|part| {
+            let words = part.split_whitespace().collect::<Vec<_>>();
+            if words.iter().all(|word| word.contains('=')) { words } else { vec![part.trim()] }
+        })
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| pair.split_once('=').map(|(key, value)| (key.trim().to_lowercase(), value.trim())))
+        .collect::<Option<Vec<_>>>();
+    let Some(pairs) = pairs.filter(|pairs| !pairs.is_empty()) else { return false };
+    if !pairs.iter().all(|(key, _)| matches!(key.as_str(), "lang" | "language" | "sentences" | "title"))
+    {
+        return false;
+    }
+
+    for (key, value) in pairs
+    {
+        match key.as_str()
+        {
+            "lang" | "language" => {
+                if !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                {
+                    entry.language = Some(value.to_lowercase());
+                }
+                else
+                {
+                    warnings.push(format!("\"{}\": {} needs a language code like \"fr\", not \"{}\"", line, key, value));
+                }
+            },
+            "sentences" => {
+                match value.parse::<usize>()
+                {
+                    Ok(count) if count > 0 => entry.sentences = Some(count),
+                    _ => warnings.push(format!("\"{}\": sentences needs a number above 0, not \"{}\"", line, value)),
+                }
+            },
+            _ => {
+                if !value.is_empty()
+                {
+                    entry.pinned_title = Some(value.to_string());
+                }
+                else
+                {
+                    warnings.push(format!("\"{}\": title needs an article title", line));
+                }
+            },
+        }
+    }
+    true
+}
+
+pub(crate) fn indent_width(line: &str) -> usize
+{
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+/// The section line `i` starts, if it is a header. One that says so, like `Causes:` or
+/// `Unit 3: WWII`, only needs a topic after it; a bare `Week 2` needs indented lines under it.
+fn header(lines: &[&str], i: usize) -> Option<String>
+{
+    let (title, marked) = section_title(lines[i].trim())?;
+    // `Mitosis:` over a blank line or another `Meiosis:` is a topic, not a header.
+    let next = lines[i + 1..].iter().find(|line| !line.trim().is_empty()).filter(|next| !is_blank(next))?;
+    let indented = indent_width(next) > indent_width(lines[i]);
+    (indented || (marked && !next.trim_end().ends_with(':'))).then_some(title)
+}
+
+/// The section a header line would name, `None` if the line can only be a topic, and whether
+/// it is marked as a header by a colon or dash.
+fn section_title(text: &str) -> Option<(String, bool)>
+{
+    if let Some(title) = text.strip_suffix(':')
+    {
+        let title = title.trim();
+        return (!title.is_empty()).then(|| (title.to_string(), true));
+    }
+
+    // `Unit 3: WWII`, `Chapter IV - Rome`, `Week 2`
+    let mut words = text.split_whitespace();
+    let word = words.next()?.to_lowercase();
+    let word_after = words.next()?;
+    let number = word_after.trim_end_matches([':', '.', ')', '-']);
+    let is_number = !number.is_empty()
+        && (number.chars().all(|c| c.is_ascii_digit() || c == '.')
+            || number.chars().all(|c| "IVXLC".contains(c))
+            || (number.len() == 1 && number.chars().all(|c| c.is_ascii_uppercase())));
+    let marked = number.len() < word_after.len() || words.next().is_some_and(|word| matches!(word, "-" | "–" | "—" | ":"));
+    (SECTION_WORDS.contains(&word.as_str()) && is_number).then(|| (text.to_string(), marked))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn titles(guide: &StudyGuide) -> Vec<Option<&str>>
+    {
+        guide.entries.iter().map(|entry| guide.section_title(entry)).collect()
+    }
+
+    #[test]
+    fn plain_list()
+    {
+        assert_eq!(parse_topics("World War II\nThe Civil War\nThe Hundreds Year War\n"), ["World War II", "The Civil War", "The Hundreds Year War"]);
+    }
+
+    #[test]
+    fn headers()
+    {
+        let guide = parse("Unit 3: WWII\n    Pearl Harbor\n    D-Day\nCauses:\n\tTreaty of Versailles\n");
+        assert_eq!(guide.sections.len(), 2);
+        assert_eq!(titles(&guide), [Some("Unit 3: WWII"), Some("Unit 3: WWII"), Some("Causes")]);
+        assert!(guide.entries.iter().all(|entry| entry.depth == 0));
+
+        let guide = parse("Unit 3: WWII\nPearl Harbor\nD-Day\nChapter IV - Rome\nJulius Caesar\nCauses:\nTreaty of Versailles\nWeek 2\n    Mitosis\n");
+        assert_eq!(titles(&guide), [Some("Unit 3: WWII"), Some("Unit 3: WWII"), Some("Chapter IV - Rome"), Some("Causes"), Some("Week 2")]);
+        assert!(guide.entries.iter().all(|entry| entry.depth == 0));
+    }
+
+    #[test]
+    fn colon_over_blank_is_topic()
+    {
+        let guide = parse("Mitosis:\n______\nMeiosis:\n______\n");
+        assert!(guide.sections.is_empty());
+        assert_eq!(guide.entries.iter().map(|entry| entry.query.as_str()).collect::<Vec<_>>(), ["Mitosis", "Meiosis"]);
+        assert_eq!(guide.entries.iter().map(|entry| entry.origin).collect::<Vec<_>>(), [Some(0), Some(2)]);
+        assert_eq!(parse_topics("Mitosis:\nMeiosis:\n"), ["Mitosis", "Meiosis"]);
+    }
+
+    #[test]
+    fn section_words_without_indent_are_topics()
+    {
+        assert_eq!(parse_topics("Day 1\nPart A\nWeek 2"), ["Day 1", "Part A", "Week 2"]);
+        assert_eq!(parse_topics("Section 8 Housing\nRent control"), ["Section 8 Housing", "Rent control"]);
+    }
+
+    #[test]
+    fn nesting_and_blank_lines()
+    {
+        let guide = parse("World War II\n    Pearl Harbor\n        USS Arizona\n    D-Day\n\nHolocaust\n");
+        assert_eq!(guide.entries.iter().map(|entry| entry.depth).collect::<Vec<_>>(), [0, 1, 2, 1, 0]);
+        assert_eq!(guide.entries[4].section, Some(0));
+        assert_eq!(guide.sections[0].title, None);
+    }
+
+    #[test]
+    fn comments()
+    {
+        assert_eq!(parse_topics("# Second semester\nD-Day       # the Normandy landings\nC#\nSymphony #5\nF # minor\n"), ["D-Day", "C#", "Symphony #5", "F # minor"]);
+        assert_eq!(parse_topics("Mercury\t# planet"), ["Mercury"]);
+    }
+
+    #[test]
+    fn blanks_after_topics()
+    {
+        assert_eq!(split_blank("Mitosis: ______"), ("Mitosis", Some("______")));
+        assert_eq!(split_blank("Mitosis"), ("Mitosis", None));
+        assert_eq!(parse_topics("Mitosis - ......\n_____\n"), ["Mitosis"]);
+        assert_eq!(split_blank("And Then There Were None..."), ("And Then There Were None...", None));
+        assert_eq!(split_blank("And Then There Were None ....."), ("And Then There Were None", Some(".....")));
+        assert_eq!(split_blank("Mitosis:......"), ("Mitosis", Some("......")));
+        assert_eq!(split_blank("Mitosis___"), ("Mitosis", Some("___")));
+    }
+
+    #[test]
+    fn directives()
+    {
+        let guide = parse("The Civil War => American Civil War\nMercury [lang=FR]\nPhotosynthesis {sentences=3}\nSonata [music]\nRome [title=Ancient Rome; sentences=2]\n");
+        let entries = &guide.entries;
+        assert_eq!(entries[0].query, "The Civil War");
+        assert_eq!(entries[0].pinned_title.as_deref(), Some("American Civil War"));
+        assert_eq!(entries[1].query, "Mercury");
+        assert_eq!(entries[1].language.as_deref(), Some("fr"));
+        assert_eq!(entries[2].sentences, Some(3));
+        assert_eq!(entries[3].query, "Sonata [music]");
+        assert_eq!(entries[4].pinned_title.as_deref(), Some("Ancient Rome"));
+        assert_eq!(entries[4].sentences, Some(2));
+    }
+
+    #[test]
+    fn directive_values_are_checked()
+    {
+        let guide = parse("Mercury [lang=f r]\nRome {sentences=0}\nVenus [lang=fr]\n");
+        assert_eq!(guide.entries[0].query, "Mercury");
+        assert_eq!(guide.entries[0].language, None);
+        assert_eq!(guide.entries[1].query, "Rome");
+        assert_eq!(guide.entries[1].sentences, None);
+        assert_eq!(guide.warnings, [
+            "\"Mercury [lang=f r]\": lang needs a language code like \"fr\", not \"f r\"",
+            "\"Rome {sentences=0}\": sentences needs a number above 0, not \"0\"",
+        ]);
+    }
+}
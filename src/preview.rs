@@ -0,0 +1,48 @@
+use crate::guide::TopicEntry;
+use crate::source::SummarySource;
+
+/// What the candidate picker shows about an article before it is chosen.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Candidate
+{
+    pub title: String,
+    /// The text of the article around the search match.
+    pub snippet: Option<String>,
+    pub description: Option<String>,
+    /// Size of the article's source in bytes.
+    pub length: Option<u64>,
+}
+
+impl Candidate
+{
+    /// The article's size for display, e.g. `12.5 kB`.
+    pub fn size(&self) -> Option<String>
+    {
+        self.length.map(|length| format!("{:.1} kB", length as f64 / 1000.0))
+    }
+}
+
+/// Looks up the snippet, description and length of every candidate of `entry`, in the same order.
+/// Whatever cannot be looked up is left out, so the picker still has the titles.
+pub async fn candidates<S: SummarySource>(source: &S, entry: &TopicEntry) -> Vec<Candidate>
+{
+    let options = entry.lookup();
+    let snippets = source.snippets(&entry.query, &options).await.unwrap_or_default();
+    let mut candidates = Vec::with_capacity(entry.candidates.len());
+    for title in &entry.candidates
+    {
+        let metadata = source.metadata(title, &options).await.ok();
+        candidates.push(Candidate {
+            title: title.clone(),
+            snippet: snippets.iter()
+                .find(|(result, _)| result == title)
+                .map(|(_, snippet)| snippet.clone())
+                .filter(|snippet| !snippet.is_empty()),
+            description: metadata.as_ref()
+                .and_then(|metadata| metadata.description.clone())
+                .filter(|description| !description.is_empty()),
+            length: metadata.and_then(|metadata| metadata.length),
+        });
+    }
+    candidates
+}
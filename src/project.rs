@@ -0,0 +1,240 @@
+use std::fmt;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use crate::fetch::FetchOptions;
+use crate::guide::StudyGuide;
+use crate::import::{ImportOptions, InputFormat};
+use crate::source::wikipedia;
+use crate::writer::{Format, WriteOptions};
+
+/// Projects are saved as `guide.sgf.json`.
+pub const EXTENSION: &str = "sgf.json";
+/// Version of the project schema written by this build.
+pub const VERSION: u64 = 2;
+
+/// Upgrades a project from version `i + 1` to `i + 2`, for every version there has been.
+/// Fields added later need no migration, they are defaulted when missing.
+const MIGRATIONS: [fn(&mut Value); VERSION as usize - 1] = [credit_wikipedia];
+
+/// Everything needed to pick up the work again: the opened file, the topics with their
+/// candidates, chosen articles and summaries, and the export settings.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Project
+{
+    pub version: u64,
+    pub guide: StudyGuide,
+    pub input: Option<ProjectInput>,
+    /// Where the summaries were fetched from.
+    pub source: Option<String>,
+    pub language: Option<String>,
+    #[serde(with = "format_name")]
+    pub format: Format,
+    pub write_options: WriteOptions,
+    pub concurrency: usize,
+}
+
+/// The file the topics were read from, kept to re-import it or fill it in place.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectInput
+{
+    pub title: Option<String>,
+    pub format: InputFormat,
+    pub options: ImportOptions,
+    #[serde(with = "base64_bytes")]
+    pub content: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum ProjectError
+{
+    Json(serde_json::Error),
+    /// Not a project at all, e.g. a topic list in JSON.
+    NotAProject,
+    /// Saved by a later build with this schema version, which this one can't be sure to read.
+    TooNew(u64),
+}
+
+impl fmt::Display for ProjectError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            ProjectError::Json(e) => write!(f, "Invalid project file: {}", e),
+            ProjectError::NotAProject => write!(f, "Not a project file"),
+            ProjectError::TooNew(version) => write!(f, "The project was saved by a newer version of the app (project version {}, this one reads up to {})", version, VERSION),
+        }
+    }
+}
+
+impl std::error::Error for ProjectError {}
+
+impl Default for Project
+{
+    fn default() -> Project
+    {
+        Project {
+            version: VERSION,
+            guide: StudyGuide::default(),
+            input: None,
+            source: None,
+            language: None,
+            format: Format::default(),
+            write_options: WriteOptions::default(),
+            concurrency: FetchOptions::default().concurrency,
+        }
+    }
+}
+
+impl Project
+{
+    /// Whether a file name looks like a saved project.
+    pub fn is_project(file_name: &str) -> bool
+    {
+        file_name.to_lowercase().ends_with(&format!(".{}", EXTENSION))
+    }
+
+    /// Reads a project saved by this or an earlier version, migrating it as needed.
+    pub fn from_json(content: &str) -> Result<Project, ProjectError>
+    {
+        let mut value = serde_json::from_str::<Value>(content).map_err(ProjectError::Json)?;
+        let Some(version) = value.get("version").and_then(Value::as_u64).filter(|version| *version > 0) else {
+            return Err(ProjectError::NotAProject);
+        };
+        if version > VERSION
+        {
+            return Err(ProjectError::TooNew(version));
+        }
+        for migrate in MIGRATIONS.iter().skip(version as usize - 1)
+        {
+            migrate(&mut value);
+        }
+        let mut project = serde_json::from_value::<Project>(value).map_err(ProjectError::Json)?;
+        project.version = VERSION;
+        Ok(project)
+    }
+
+    pub fn to_json(&self) -> Result<String, ProjectError>
+    {
+        serde_json::to_string_pretty(self).map_err(ProjectError::Json)
+    }
+
+    /// Suggested file name, from the guide's title.
+    pub fn file_name(&self) -> String
+    {
+        let title = self.guide.title.as_deref().filter(|title| !title.is_empty()).unwrap_or("guide");
+        format!("{}.{}", title, EXTENSION)
+    }
+}
+
+/// Version 1 kept no attribution, its summaries could only have come from Wikipedia.
+fn credit_wikipedia(project: &mut Value)
+{
+    let language = project["language"].as_str().unwrap_or("en").to_string();
+    let Some(guide) = project.get_mut("guide").and_then(Value::as_object_mut) else { return };
+    let fetched = guide.get("entries")
+        .and_then(Value::as_array)
+        .is_some_and(|entries| entries.iter().any(|entry| entry["summary"].is_string()));
+    if fetched && !guide.contains_key("attribution")
+    {
+        guide.insert("attribution".to_string(), serde_json::to_value(wikipedia::attribution(&language)).unwrap_or_default());
+    }
+}
+
+/// The export format by name, so a project using a format this build lacks still opens.
+mod format_name
+{
+    use super::*;
+
+    pub fn serialize<S: Serializer>(format: &Format, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        serializer.serialize_str(format.extension())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Format, D::Error>
+    {
+        let name = String::deserialize(deserializer)?;
+        Ok(Format::from_extension(&name).unwrap_or_default())
+    }
+}
+
+mod base64_bytes
+{
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+    {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    {
+        let text = String::deserialize(deserializer)?;
+        STANDARD.decode(text).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn version_1_projects_credit_wikipedia()
+    {
+        let content = r#"{"version": 1, "language": "de", "guide": {"entries": [{"query": "Mitosis", "summary": "Zellteilung."}]}}"#;
+        let project = Project::from_json(content).unwrap();
+        assert_eq!(project.version, VERSION);
+        assert_eq!(project.guide.attribution, Some(wikipedia::attribution("de")));
+
+        let unfetched = Project::from_json(r#"{"version": 1, "guide": {"entries": [{"query": "Mitosis"}]}}"#).unwrap();
+        assert_eq!(unfetched.guide.attribution, None);
+    }
+
+    #[test]
+    fn round_trip()
+    {
+        let mut project = Project {
+            guide: StudyGuide::from_topics(vec!["Mitosis".to_string(), "Meiosis".to_string()]),
+            input: Some(ProjectInput {
+                title: Some("Biology".to_string()),
+                format: InputFormat::Docx,
+                options: ImportOptions::default(),
+                content: vec![0, 159, 255, b'P', b'K'],
+            }),
+            language: Some("fr".to_string()),
+            format: Format::Pdf,
+            concurrency: 2,
+            ..Project::default()
+        };
+        project.guide.entries[0].summary = Some("Cell division.".to_string());
+        project.guide.entries[1].enabled = false;
+        project.write_options.layout.columns = 2;
+
+        let read = Project::from_json(&project.to_json().unwrap()).unwrap();
+        assert_eq!(read.version, VERSION);
+        assert_eq!(read.guide.entries[0].summary.as_deref(), Some("Cell division."));
+        assert!(!read.guide.entries[1].enabled);
+        assert_eq!(read.input.as_ref().map(|input| input.content.as_slice()), Some([0, 159, 255, b'P', b'K'].as_slice()));
+        assert_eq!(read.input.map(|input| input.format), Some(InputFormat::Docx));
+        assert_eq!(read.language.as_deref(), Some("fr"));
+        assert_eq!(read.format, Format::Pdf);
+        assert_eq!(read.write_options.layout.columns, 2);
+        assert_eq!(read.concurrency, 2);
+    }
+
+    #[test]
+    fn rejects_other_json_and_newer_versions()
+    {
+        assert!(matches!(Project::from_json(r#"["Mitosis", "Meiosis"]"#), Err(ProjectError::NotAProject)));
+        assert!(matches!(Project::from_json(r#"{"topics": ["Mitosis"]}"#), Err(ProjectError::NotAProject)));
+        assert!(matches!(Project::from_json(r#"{"version": 0, "guide": {}}"#), Err(ProjectError::NotAProject)));
+        assert!(matches!(Project::from_json("not json"), Err(ProjectError::Json(_))));
+        let newer = format!(r#"{{"version": {}, "guide": {{}}}}"#, VERSION + 1);
+        assert!(matches!(Project::from_json(&newer), Err(ProjectError::TooNew(version)) if version == VERSION + 1));
+    }
+}
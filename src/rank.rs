@@ -0,0 +1,197 @@
+use std::collections::HashSet;
+use crate::guide::StudyGuide;
+use crate::source::{LookupOptions, SummarySource};
+
+/// How many of the first search results are scored, the rest keep their order after them.
+const RANKED: usize = 5;
+/// Most titles the source is asked about at once when checking links, the topics nearest the
+/// query in the list.
+const MAX_LINKED: usize = 50;
+const LINK_WEIGHT: f32 = 2.0;
+/// Picks below this confidence are worth a second look.
+pub const LOW_CONFIDENCE: f32 = 0.5;
+
+const STOP_WORDS: [&str; 16] = [
+    "the", "and", "for", "from", "with", "that", "this", "into", "its", "are",
+    "was", "were", "which", "also", "other", "articles",
+];
+
+/// What the topic list as a whole is about: the topics themselves and the words they and the
+/// section titles use.
+#[derive(Clone, Debug, Default)]
+pub struct Context
+{
+    topics: Vec<String>,
+    terms: Vec<HashSet<String>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ranking
+{
+    /// Every candidate, the most coherent with the rest of the list first.
+    pub candidates: Vec<String>,
+    /// How far the first candidate is ahead of the second on the evidence, from 0 to 1. It is 1
+    /// when nothing speaks for any other candidate.
+    pub confidence: f32,
+}
+
+impl Context
+{
+    pub fn new(guide: &StudyGuide) -> Context
+    {
+        let mut context = Context::default();
+        for entry in guide.entries.iter().filter(|entry| entry.enabled)
+        {
+            context.topics.push(entry.query.clone());
+            context.terms.push(terms(&entry.query));
+        }
+        for title in guide.title.iter().chain(guide.sections.iter().filter_map(|section| section.title.as_ref()))
+        {
+            context.topics.push(String::new());
+            context.terms.push(terms(title));
+        }
+        context
+    }
+
+    /// The other topics nearest `query` in the list, and the words all of them use that `query`
+    /// does not.
+    fn around(&self, query: &str) -> (Vec<String>, HashSet<String>)
+    {
+        let own = terms(query);
+        let at = self.topics.iter().position(|topic| topic.eq_ignore_ascii_case(query)).unwrap_or_default();
+        let mut others = self.topics.iter()
+            .enumerate()
+            .filter(|(_, topic)| !topic.is_empty() && !topic.eq_ignore_ascii_case(query))
+            .collect::<Vec<_>>();
+        others.sort_by_key(|(i, _)| i.abs_diff(at));
+        let others = others.into_iter().take(MAX_LINKED).map(|(_, topic)| topic.clone()).collect();
+        let words = self.terms.iter()
+            .flatten()
+            .filter(|word| !own.contains(*word))
+            .cloned()
+            .collect();
+        (others, words)
+    }
+}
+
+/// Orders the candidates for `query` by how well they fit the other topics: whether the article
+/// links to them, and how many of their words its title, description and categories share.
+///
+/// The search order breaks ties. Candidates whose details cannot be fetched only get that.
+pub async fn rank<S: SummarySource>(source: &S, query: &str, candidates: Vec<String>, context: &Context, options: &LookupOptions) -> Ranking
+{
+    if candidates.len() < 2
+    {
+        return Ranking { candidates, confidence: 1.0 };
+    }
+
+    let (others, words) = context.around(query);
+    let mut scored = Vec::new();
+    let mut evidence = false;
+    for (position, title) in candidates.iter().take(RANKED).enumerate()
+    {
+        let mut fit = 0.0;
+        if let Ok(metadata) = source.metadata(title, options).await
+        {
+            let mut about = terms(&metadata.title);
+            about.extend(metadata.description.iter().flat_map(|description| terms(description)));
+            about.extend(metadata.categories.iter().flat_map(|category| terms(category)));
+            fit += about.intersection(&words).count() as f32;
+        }
+        if !others.is_empty()
+        {
+            if let Ok(linked) = source.linked(title, &others, options).await
+            {
+                fit += LINK_WEIGHT * linked.len() as f32;
+            }
+        }
+        evidence |= fit > 0.0;
+        scored.push((title.clone(), fit + 1.0 / (1.0 + position as f32)));
+    }
+    scored.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    // Without anything to go on the search order stands, and there is no reason to doubt it.
+    let confidence = match scored.as_slice()
+    {
+        [(_, first), (_, second), ..] if evidence => (first - second) / first,
+        _ => 1.0,
+    };
+    let mut ranked = scored.into_iter().map(|(title, _)| title).collect::<Vec<_>>();
+    ranked.extend(candidates.into_iter().skip(RANKED));
+    Ranking { candidates: ranked, confidence }
+}
+
+/// The lowercase words of `text` worth comparing, with a plural `s` dropped.
+fn terms(text: &str) -> HashSet<String>
+{
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| word.chars().count() > 2 && !STOP_WORDS.contains(&word.as_str()))
+        .map(|word| match word.strip_suffix('s')
+        {
+            Some(stem) if stem.chars().count() > 3 && !stem.ends_with('s') => stem.to_string(),
+            _ => word,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::source::fixture::FixtureSource;
+
+    fn source() -> FixtureSource
+    {
+        FixtureSource::new()
+            .with_page("Mercury (element)", "A chemical element, the only metal that is liquid.")
+            .with_page("Mercury (planet)", "The smallest planet, the one nearest the Sun, inside the orbit of Venus.")
+            .with_page("Mercury (mythology)", "A Roman god.")
+    }
+
+    fn rank_mercury(topics: &[&str], candidates: &[&str]) -> Ranking
+    {
+        let guide = StudyGuide::from_topics(topics.iter().map(|topic| topic.to_string()).collect());
+        let candidates = candidates.iter().map(|title| title.to_string()).collect();
+        pollster::block_on(rank(&source(), "Mercury", candidates, &Context::new(&guide), &LookupOptions::default()))
+    }
+
+    #[test]
+    fn context_moves_the_fitting_title_up()
+    {
+        let ranking = rank_mercury(&["Mercury", "Venus", "Sun"], &["Mercury (element)", "Mercury (mythology)", "Mercury (planet)"]);
+        assert_eq!(ranking.candidates[0], "Mercury (planet)");
+        assert!(ranking.confidence >= LOW_CONFIDENCE, "{}", ranking.confidence);
+    }
+
+    #[test]
+    fn picks_without_evidence_against_them_are_not_flagged()
+    {
+        let candidates = ["Mercury (element)", "Mercury (mythology)", "Mercury (planet)"];
+        let ranking = rank_mercury(&["Mercury", "Photosynthesis"], &candidates);
+        assert_eq!(ranking.candidates, candidates);
+        assert_eq!(ranking.confidence, 1.0);
+
+        let ranking = rank_mercury(&["Mercury", "Venus"], &["Mercury (planet)"]);
+        assert_eq!(ranking.confidence, 1.0);
+    }
+
+    #[test]
+    fn close_picks_are_flagged()
+    {
+        let ranking = rank_mercury(&["Mercury", "Metal", "Planet"], &["Mercury (element)", "Mercury (planet)"]);
+        assert!(ranking.confidence < LOW_CONFIDENCE, "{}", ranking.confidence);
+    }
+
+    #[test]
+    fn context_is_the_nearest_topics()
+    {
+        let mut topics = (0..2 * MAX_LINKED).map(|i| format!("Topic {}", i)).collect::<Vec<_>>();
+        topics.push("Mercury".to_string());
+        let guide = StudyGuide::from_topics(topics);
+        let (others, _) = Context::new(&guide).around("Mercury");
+        assert_eq!(others.len(), MAX_LINKED);
+        assert_eq!(others[0], format!("Topic {}", 2 * MAX_LINKED - 1));
+        assert!(!others.contains(&"Topic 0".to_string()));
+    }
+}
@@ -0,0 +1,137 @@
+use crate::source::{Attribution, LookupOptions, PageMetadata, SourceError, Summary, SummarySource};
+
+const SNIPPET_LENGTH: usize = 150;
+
+/// In-memory source answering from a fixed set of pages, for offline use and tests.
+/// Every language gets the same pages.
+#[derive(Clone, Debug, Default)]
+pub struct FixtureSource
+{
+    pages: Vec<(String, String)>,
+    /// Disambiguation pages and the titles they list.
+    disambiguations: Vec<(String, Vec<String>)>,
+}
+
+impl FixtureSource
+{
+    pub fn new() -> FixtureSource
+    {
+        FixtureSource::default()
+    }
+
+    pub fn with_page(mut self, title: impl Into<String>, text: impl Into<String>) -> FixtureSource
+    {
+        self.pages.push((title.into(), text.into()));
+        self
+    }
+
+    pub fn with_disambiguation(mut self, title: impl Into<String>, meanings: &[&str]) -> FixtureSource
+    {
+        let title = title.into();
+        self.pages.push((title.clone(), format!("{} may refer to: {}", title, meanings.join(", "))));
+        self.disambiguations.push((title, meanings.iter().map(|meaning| meaning.to_string()).collect()));
+        self
+    }
+
+    fn meanings_of(&self, title: &str) -> Option<&Vec<String>>
+    {
+        self.disambiguations.iter()
+            .find(|(page, _)| page.eq_ignore_ascii_case(title))
+            .map(|(_, meanings)| meanings)
+    }
+
+    fn page(&self, title: &str) -> Result<&(String, String), SourceError>
+    {
+        self.pages.iter()
+            .find(|(page, _)| page.eq_ignore_ascii_case(title))
+            .ok_or_else(|| SourceError::MissingPage(title.to_string()))
+    }
+}
+
+impl SummarySource for FixtureSource
+{
+    fn name(&self) -> &str
+    {
+        "fixture"
+    }
+
+    fn language(&self) -> &str
+    {
+        "en"
+    }
+
+    fn attribution(&self) -> Attribution
+    {
+        Attribution {
+            name: "Fixture".to_string(),
+            url: "https://example.org/".to_string(),
+            license: "CC0 1.0".to_string(),
+            license_url: "https://creativecommons.org/publicdomain/zero/1.0/".to_string(),
+        }
+    }
+
+    async fn search(&self, query: &str, _options: &LookupOptions) -> Result<Vec<String>, SourceError>
+    {
+        let query = query.to_lowercase();
+        let (mut titles, texts): (Vec<_>, Vec<_>) = self.pages.iter()
+            .filter(|(title, text)| title.to_lowercase().contains(&query) || text.to_lowercase().contains(&query))
+            .partition(|(title, _)| title.to_lowercase().contains(&query));
+        titles.extend(texts);
+        Ok(titles.into_iter().map(|(title, _)| title.clone()).collect())
+    }
+
+    /// The start of each matching page's text stands in for the snippet.
+    async fn snippets(&self, query: &str, options: &LookupOptions) -> Result<Vec<(String, String)>, SourceError>
+    {
+        let titles = self.search(query, options).await?;
+        Ok(titles.into_iter()
+            .filter_map(|title| {
+                let (_, text) = self.page(&title).ok()?;
+                Some((title, text.chars().take(SNIPPET_LENGTH).collect()))
+            })
+            .collect())
+    }
+
+    async fn summary(&self, title: &str, _options: &LookupOptions) -> Result<Summary, SourceError>
+    {
+        let (title, text) = self.page(title)?;
+        Ok(Summary {
+            title: title.clone(),
+            text: text.clone(),
+            url: String::new(),
+            revision: None,
+            disambiguation: self.meanings_of(title).is_some(),
+        })
+    }
+
+    async fn metadata(&self, title: &str, _options: &LookupOptions) -> Result<PageMetadata, SourceError>
+    {
+        let (title, text) = self.page(title)?;
+        Ok(PageMetadata {
+            title: title.clone(),
+            length: Some(text.len() as u64),
+            disambiguation: self.meanings_of(title).is_some(),
+            ..PageMetadata::default()
+        })
+    }
+
+    async fn meanings(&self, title: &str, _options: &LookupOptions) -> Result<Vec<String>, SourceError>
+    {
+        self.page(title)?;
+        Ok(self.meanings_of(title).cloned().unwrap_or_default())
+    }
+
+    /// Every page title, for the caller to pick the close ones.
+    async fn suggestions(&self, _query: &str, _options: &LookupOptions) -> Result<Vec<String>, SourceError>
+    {
+        Ok(self.pages.iter().map(|(title, _)| title.clone()).collect())
+    }
+
+    /// Counts a target as linked when the page's text mentions it.
+    async fn linked(&self, title: &str, targets: &[String], _options: &LookupOptions) -> Result<Vec<String>, SourceError>
+    {
+        let (_, text) = self.page(title)?;
+        let text = text.to_lowercase();
+        Ok(targets.iter().filter(|target| text.contains(&target.to_lowercase())).cloned().collect())
+    }
+}
@@ -0,0 +1,163 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+
+pub mod fixture;
+pub mod wikipedia;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SourceError
+{
+    Network(String),
+    Status(u16),
+    Api { code: String, info: String },
+    Json(String),
+    MissingPage(String),
+    NotCached(String),
+    /// The lookup was cancelled before it finished.
+    Cancelled,
+}
+
+impl SourceError
+{
+    /// Errors worth retrying: dropped connections, server errors, rate limits and replication lag.
+    pub fn is_transient(&self) -> bool
+    {
+        match self
+        {
+            SourceError::Network(_) => true,
+            SourceError::Status(status) => *status == 429 || *status >= 500,
+            SourceError::Api { code, .. } => code == "maxlag" || code == "ratelimited",
+            SourceError::Json(_) | SourceError::MissingPage(_) | SourceError::NotCached(_) | SourceError::Cancelled => false,
+        }
+    }
+}
+
+impl fmt::Display for SourceError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            SourceError::Network(message) => write!(f, "Network error: {}", message),
+            SourceError::Status(429) => write!(f, "Rate limited by the server"),
+            SourceError::Status(status) => write!(f, "HTTP status {}", status),
+            SourceError::Api { code, info } => write!(f, "API error {}: {}", code, info),
+            SourceError::Json(message) => write!(f, "Invalid response: {}", message),
+            SourceError::MissingPage(title) => write!(f, "No page titled \"{}\"", title),
+            SourceError::NotCached(title) => write!(f, "\"{}\" is not in the offline cache", title),
+            SourceError::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Summary
+{
+    /// Title of the article after following redirects.
+    pub title: String,
+    pub text: String,
+    pub url: String,
+    pub revision: Option<u64>,
+    /// The page only lists the articles the title may refer to.
+    #[serde(default)]
+    pub disambiguation: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PageMetadata
+{
+    pub title: String,
+    pub url: String,
+    pub revision: Option<u64>,
+    pub description: Option<String>,
+    pub length: Option<u64>,
+    pub categories: Vec<String>,
+    pub disambiguation: bool,
+}
+
+/// Who wrote the summaries and under which licence they may be shared, credited in the footer
+/// of every export.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Attribution
+{
+    /// E.g. `Wikipedia`.
+    pub name: String,
+    pub url: String,
+    /// E.g. `CC BY-SA 4.0`.
+    pub license: String,
+    pub license_url: String,
+}
+
+/// How one topic is looked up.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LookupOptions
+{
+    /// Language edition to use instead of [`SummarySource::language`].
+    pub language: Option<String>,
+    /// Stops retries and their backoff once the lookup is no longer wanted.
+    pub cancel: Cancel,
+}
+
+/// A flag shared by the lookups of one fetch, set when they are no longer wanted.
+#[derive(Clone, Debug, Default)]
+pub struct Cancel(Arc<AtomicBool>);
+
+impl Cancel
+{
+    pub fn cancel(&self)
+    {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool
+    {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl PartialEq for Cancel
+{
+    fn eq(&self, other: &Cancel) -> bool
+    {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Somewhere topics can be looked up and summarized.
+#[allow(async_fn_in_trait)]
+pub trait SummarySource
+{
+    /// Short identifier of the backend, e.g. `wikipedia`.
+    fn name(&self) -> &str;
+
+    /// The default language, used unless [`LookupOptions::language`] says otherwise.
+    fn language(&self) -> &str;
+
+    /// Who to credit for the summaries.
+    fn attribution(&self) -> Attribution;
+
+    /// Returns the titles of the articles matching `query`, best match first.
+    async fn search(&self, query: &str, options: &LookupOptions) -> Result<Vec<String>, SourceError>;
+
+    /// Returns the titles of the articles matching `query` like [`SummarySource::search`], each
+    /// with the plain text around the match.
+    async fn snippets(&self, query: &str, options: &LookupOptions) -> Result<Vec<(String, String)>, SourceError>;
+
+    async fn summary(&self, title: &str, options: &LookupOptions) -> Result<Summary, SourceError>;
+
+    async fn metadata(&self, title: &str, options: &LookupOptions) -> Result<PageMetadata, SourceError>;
+
+    /// Returns the titles of the articles a disambiguation page lists.
+    async fn meanings(&self, title: &str, options: &LookupOptions) -> Result<Vec<String>, SourceError>;
+
+    /// Returns spellings proposed for `query`, best first, which may be titles or search terms.
+    async fn suggestions(&self, query: &str, options: &LookupOptions) -> Result<Vec<String>, SourceError>;
+
+    /// Returns which of `targets` the article links to.
+    async fn linked(&self, title: &str, targets: &[String], options: &LookupOptions) -> Result<Vec<String>, SourceError>;
+}
@@ -0,0 +1,640 @@
+use std::time::Duration;
+use serde_json::Value;
+use crate::cache;
+use crate::xml::{tags, unescape};
+use crate::source::{Attribution, Cancel, LookupOptions, PageMetadata, SourceError, Summary, SummarySource};
+
+pub const DEFAULT_API_URL: &str = "https://{language}.wikipedia.org/w/api.php";
+#[cfg(not(target_arch = "wasm32"))]
+const USER_AGENT: &str = concat!("study-guide-filler/", env!("CARGO_PKG_VERSION"), " (https://github.com/Nvt500/Study-Guide-Filler)");
+const MAX_LAG: &str = "5";
+const DISAMBIGUATION_CATEGORIES: &str = "Category:All disambiguation pages|Category:Disambiguation pages";
+/// How often a backoff checks whether it was cancelled.
+const BACKOFF_STEP: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Debug)]
+pub struct RetryPolicy
+{
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy
+{
+    fn default() -> RetryPolicy
+    {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy
+{
+    /// Exponential backoff: `base_delay * 2^attempt`, capped at `max_delay`.
+    pub fn delay(&self, attempt: u32) -> Duration
+    {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay)
+    }
+}
+
+struct Response
+{
+    status: u16,
+    retry_after: Option<Duration>,
+    body: String,
+}
+
+/// Minimal client for the MediaWiki action API.
+///
+/// `api_url` may contain `{language}`, so it can be pointed at a local mock server.
+pub struct Wikipedia
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    client: reqwest::blocking::Client,
+    #[cfg(target_arch = "wasm32")]
+    client: reqwest::Client,
+    api_url: String,
+    language: String,
+    retry: RetryPolicy,
+}
+
+impl Default for Wikipedia
+{
+    fn default() -> Wikipedia
+    {
+        Wikipedia::new("en")
+    }
+}
+
+impl Wikipedia
+{
+    pub fn new(language: &str) -> Wikipedia
+    {
+        #[cfg(not(target_arch = "wasm32"))]
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(USER_AGENT)
+            .build()
+            .unwrap_or_default();
+        #[cfg(target_arch = "wasm32")]
+        let client = reqwest::Client::new();
+
+        Wikipedia {
+            client,
+            api_url: DEFAULT_API_URL.to_string(),
+            language: language.to_string(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Wikipedia
+    {
+        self.api_url = api_url.into();
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Wikipedia
+    {
+        self.retry = retry;
+        self
+    }
+
+    pub fn api_url(&self) -> String
+    {
+        self.api_url_for(&self.language)
+    }
+
+    pub fn api_url_for(&self, language: &str) -> String
+    {
+        self.api_url.replace("{language}", language)
+    }
+
+    /// Runs an API query against the default language's wiki, see [`Wikipedia::query_in`].
+    pub async fn query(&self, params: &[(&str, &str)]) -> Result<Value, SourceError>
+    {
+        self.query_in(&self.language, params).await
+    }
+
+    /// Runs an API query, retrying transient failures with exponential backoff.
+    ///
+    /// A `Retry-After` header sent with 429, 503 or `maxlag` responses, in seconds or as an HTTP
+    /// date, takes precedence over the backoff.
+    pub async fn query_in(&self, language: &str, params: &[(&str, &str)]) -> Result<Value, SourceError>
+    {
+        self.query_until(language, params, &Cancel::default()).await
+    }
+
+    /// Runs an API query for a topic, in its language and until it is cancelled.
+    async fn lookup(&self, options: &LookupOptions, params: &[(&str, &str)]) -> Result<Value, SourceError>
+    {
+        self.query_until(self.language_for(options), params, &options.cancel).await
+    }
+
+    /// Like [`Wikipedia::query_in`], but gives up with [`SourceError::Cancelled`] as soon as
+    /// `cancel` is set, rather than sending another request or waiting out the backoff.
+    async fn query_until(&self, language: &str, params: &[(&str, &str)], cancel: &Cancel) -> Result<Value, SourceError>
+    {
+        let api_url = self.api_url_for(language);
+        let mut attempt = 0;
+        loop
+        {
+            if cancel.is_cancelled()
+            {
+                return Err(SourceError::Cancelled);
+            }
+            match self.request(&api_url, params).await
+            {
+                Ok(value) => return Ok(value),
+                Err((error, retry_after)) if error.is_transient() && attempt < self.retry.max_retries => {
+                    let delay = retry_after.unwrap_or_else(|| self.retry.delay(attempt));
+                    backoff(delay.min(self.retry.max_delay), cancel).await;
+                    attempt += 1;
+                },
+                Err((error, _)) => return Err(error),
+            }
+        }
+    }
+
+    async fn request(&self, api_url: &str, params: &[(&str, &str)]) -> Result<Value, (SourceError, Option<Duration>)>
+    {
+        let mut params = params.to_vec();
+        params.extend([
+            ("format", "json"),
+            ("formatversion", "2"),
+            ("maxlag", MAX_LAG),
+            ("origin", "*"),
+        ]);
+        let url = reqwest::Url::parse_with_params(api_url, &params)
+            .map_err(|e| (SourceError::Network(e.to_string()), None))?;
+
+        let response = self.send(url).await.map_err(|e| (e, None))?;
+        if !(200..300).contains(&response.status)
+        {
+            return Err((SourceError::Status(response.status), response.retry_after));
+        }
+
+        let value = serde_json::from_str::<Value>(&response.body)
+            .map_err(|e| (SourceError::Json(e.to_string()), None))?;
+        if let Some(error) = value.get("error")
+        {
+            let code = error["code"].as_str().unwrap_or_default().to_string();
+            let info = error["info"].as_str().unwrap_or_default().to_string();
+            return Err((SourceError::Api { code, info }, response.retry_after));
+        }
+        Ok(value)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn send(&self, url: reqwest::Url) -> Result<Response, SourceError>
+    {
+        let response = self.client.get(url)
+            .send()
+            .map_err(|e| SourceError::Network(e.to_string()))?;
+        let status = response.status().as_u16();
+        let retry_after = retry_after(response.headers());
+        let body = response.text().map_err(|e| SourceError::Network(e.to_string()))?;
+        Ok(Response { status, retry_after, body })
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn send(&self, url: reqwest::Url) -> Result<Response, SourceError>
+    {
+        let response = self.client.get(url)
+            .send()
+            .await
+            .map_err(|e| SourceError::Network(e.to_string()))?;
+        let status = response.status().as_u16();
+        let retry_after = retry_after(response.headers());
+        let body = response.text().await.map_err(|e| SourceError::Network(e.to_string()))?;
+        Ok(Response { status, retry_after, body })
+    }
+
+    fn language_for<'a>(&'a self, options: &'a LookupOptions) -> &'a str
+    {
+        options.language.as_deref().unwrap_or(&self.language)
+    }
+}
+
+impl SummarySource for Wikipedia
+{
+    fn name(&self) -> &str
+    {
+        "wikipedia"
+    }
+
+    fn language(&self) -> &str
+    {
+        &self.language
+    }
+
+    fn attribution(&self) -> Attribution
+    {
+        attribution(&self.language)
+    }
+
+    async fn search(&self, query: &str, options: &LookupOptions) -> Result<Vec<String>, SourceError>
+    {
+        let value = self.lookup(options, &[
+            ("action", "query"),
+            ("list", "search"),
+            ("srsearch", query),
+            ("srlimit", "10"),
+            ("srprop", ""),
+        ]).await?;
+
+        Ok(value["query"]["search"]
+            .as_array()
+            .map(|results| results.iter()
+                .filter_map(|result| result["title"].as_str().map(|title| title.to_string()))
+                .collect())
+            .unwrap_or_default())
+    }
+
+    async fn snippets(&self, query: &str, options: &LookupOptions) -> Result<Vec<(String, String)>, SourceError>
+    {
+        let value = self.lookup(options, &[
+            ("action", "query"),
+            ("list", "search"),
+            ("srsearch", query),
+            ("srlimit", "10"),
+            ("srprop", "snippet"),
+        ]).await?;
+
+        Ok(value["query"]["search"]
+            .as_array()
+            .map(|results| results.iter()
+                .filter_map(|result| Some((result["title"].as_str()?.to_string(), plain_text(result["snippet"].as_str()?))))
+                .collect())
+            .unwrap_or_default())
+    }
+
+    /// Returns the plain text introduction of the article, following redirects.
+    async fn summary(&self, title: &str, options: &LookupOptions) -> Result<Summary, SourceError>
+    {
+        let value = self.lookup(options, &[
+            ("action", "query"),
+            ("prop", "extracts|info|pageprops|categories"),
+            ("exintro", "1"),
+            ("explaintext", "1"),
+            ("inprop", "url"),
+            ("ppprop", "disambiguation"),
+            ("clcategories", DISAMBIGUATION_CATEGORIES),
+            ("redirects", "1"),
+            ("titles", title),
+        ]).await?;
+
+        let page = page(&value, title)?;
+        Ok(Summary {
+            title: page["title"].as_str().unwrap_or(title).to_string(),
+            text: page["extract"]
+                .as_str()
+                .ok_or_else(|| SourceError::Json("page has no extract".to_string()))?
+                .to_string(),
+            url: page["fullurl"].as_str().unwrap_or_default().to_string(),
+            revision: page["lastrevid"].as_u64(),
+            disambiguation: is_disambiguation(page),
+        })
+    }
+
+    async fn metadata(&self, title: &str, options: &LookupOptions) -> Result<PageMetadata, SourceError>
+    {
+        let value = self.lookup(options, &[
+            ("action", "query"),
+            ("prop", "info|pageprops|categories"),
+            ("inprop", "url"),
+            ("clshow", "!hidden"),
+            ("cllimit", "max"),
+            ("redirects", "1"),
+            ("titles", title),
+        ]).await?;
+
+        let page = page(&value, title)?;
+        Ok(PageMetadata {
+            title: page["title"].as_str().unwrap_or(title).to_string(),
+            url: page["fullurl"].as_str().unwrap_or_default().to_string(),
+            revision: page["lastrevid"].as_u64(),
+            description: page["pageprops"]["wikibase-shortdesc"].as_str().map(|description| description.to_string()),
+            length: page["length"].as_u64(),
+            categories: page["categories"]
+                .as_array()
+                .map(|categories| categories.iter()
+                    .filter_map(|category| category["title"].as_str())
+                    .map(|category| category.trim_start_matches("Category:").to_string())
+                    .collect())
+                .unwrap_or_default(),
+            disambiguation: is_disambiguation(page),
+        })
+    }
+
+    /// Returns the articles the page links to, leaving out other disambiguation pages.
+    async fn meanings(&self, title: &str, options: &LookupOptions) -> Result<Vec<String>, SourceError>
+    {
+        let value = self.lookup(options, &[
+            ("action", "query"),
+            ("prop", "links"),
+            ("plnamespace", "0"),
+            ("pllimit", "max"),
+            ("redirects", "1"),
+            ("titles", title),
+        ]).await?;
+
+        let page = page(&value, title)?;
+        Ok(page["links"]
+            .as_array()
+            .map(|links| links.iter()
+                .filter_map(|link| link["title"].as_str())
+                .filter(|link| !link.ends_with("(disambiguation)"))
+                .map(|link| link.to_string())
+                .collect())
+            .unwrap_or_default())
+    }
+
+    /// Returns the titles starting like `query`, then the search engine's "did you mean" spelling.
+    async fn suggestions(&self, query: &str, options: &LookupOptions) -> Result<Vec<String>, SourceError>
+    {
+        let opensearch = self.lookup(options, &[
+            ("action", "opensearch"),
+            ("search", query),
+            ("limit", "5"),
+            ("namespace", "0"),
+        ]).await?;
+        let search = self.lookup(options, &[
+            ("action", "query"),
+            ("list", "search"),
+            ("srsearch", query),
+            ("srinfo", "suggestion"),
+            ("srlimit", "1"),
+            ("srprop", ""),
+        ]).await?;
+
+        let mut suggestions = opensearch[1]
+            .as_array()
+            .map(|titles| titles.iter()
+                .filter_map(|title| title.as_str().map(|title| title.to_string()))
+                .collect::<Vec<_>>())
+            .unwrap_or_default();
+        suggestions.extend(search["query"]["searchinfo"]["suggestion"].as_str().map(|suggestion| suggestion.to_string()));
+        Ok(suggestions)
+    }
+
+    async fn linked(&self, title: &str, targets: &[String], options: &LookupOptions) -> Result<Vec<String>, SourceError>
+    {
+        let targets = targets.join("|");
+        let value = self.lookup(options, &[
+            ("action", "query"),
+            ("prop", "links"),
+            ("pltitles", &targets),
+            ("pllimit", "max"),
+            ("redirects", "1"),
+            ("titles", title),
+        ]).await?;
+
+        let page = page(&value, title)?;
+        Ok(page["links"]
+            .as_array()
+            .map(|links| links.iter()
+                .filter_map(|link| link["title"].as_str().map(|link| link.to_string()))
+                .collect())
+            .unwrap_or_default())
+    }
+}
+
+/// Whether the page has the `disambiguation` page property, or failing that, is in one of the
+/// disambiguation categories.
+fn is_disambiguation(page: &Value) -> bool
+{
+    page["pageprops"].get("disambiguation").is_some() || page["categories"]
+        .as_array()
+        .is_some_and(|categories| categories.iter()
+            .filter_map(|category| category["title"].as_str())
+            .any(|category| DISAMBIGUATION_CATEGORIES.split('|').any(|name| name == category)))
+}
+
+/// The single page of a `titles=` query, or `MissingPage` if it does not exist.
+fn page<'a>(value: &'a Value, title: &str) -> Result<&'a Value, SourceError>
+{
+    let page = &value["query"]["pages"][0];
+    if page.is_null() || page.get("missing").is_some() || page.get("invalid").is_some()
+    {
+        return Err(SourceError::MissingPage(title.to_string()));
+    }
+    Ok(page)
+}
+
+/// A search snippet without its highlighting markup.
+fn plain_text(html: &str) -> String
+{
+    tags(html).into_iter()
+        .filter(|tag| tag.name == "#text")
+        .map(|tag| unescape(tag.text))
+        .collect()
+}
+
+/// Wikipedia in `language` and its licence, which covers the text of every article.
+pub fn attribution(language: &str) -> Attribution
+{
+    Attribution {
+        name: "Wikipedia".to_string(),
+        url: format!("https://{}.wikipedia.org/", language),
+        license: "CC BY-SA 4.0".to_string(),
+        license_url: "https://creativecommons.org/licenses/by-sa/4.0/".to_string(),
+    }
+}
+
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration>
+{
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(value, cache::now())
+}
+
+/// How long a `Retry-After` value asks to wait at `now`, in seconds since the Unix epoch.
+///
+/// Takes a number of seconds or an IMF-fixdate like `Sun, 06 Nov 1994 08:49:37 GMT`, the only
+/// date form servers may send. The obsolete RFC 850 and asctime forms are not read, so they fall
+/// back to the backoff.
+fn parse_retry_after(value: &str, now: u64) -> Option<Duration>
+{
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>()
+    {
+        return Some(Duration::from_secs(seconds));
+    }
+    http_date(value).map(|date| Duration::from_secs(date.saturating_sub(now)))
+}
+
+/// Seconds since the Unix epoch of an IMF-fixdate.
+fn http_date(value: &str) -> Option<u64>
+{
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let (_, date) = value.split_once(", ")?;
+    let mut parts = date.split(' ');
+    let day = parts.next()?.parse::<u64>().ok()?;
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|name| *name == month)? as u64 + 1;
+    let year = parts.next()?.parse::<u64>().ok()?;
+    let mut time = parts.next()?.split(':').map(|part| part.parse::<u64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    if parts.next()? != "GMT" || parts.next().is_some() || time.next().is_some()
+        || !(1..=31).contains(&day) || year < 1970 || hour > 23 || minute > 59 || second > 60
+    {
+        return None;
+    }
+    // Days since 1970-01-01, counting from March so the leap day ends the year.
+    let (year, month) = if month <= 2 { (year - 1, month + 9) } else { (year, month - 3) };
+    let era_day = (year % 400) * 365 + (year % 400) / 4 - (year % 400) / 100 + (153 * month + 2) / 5 + day - 1;
+    let days = (year / 400) * 146_097 + era_day - 719_468;
+    Some(days * 24 * 60 * 60 + hour * 60 * 60 + minute * 60 + second)
+}
+
+/// Waits `delay` in short steps, stopping early once `cancel` is set.
+async fn backoff(delay: Duration, cancel: &Cancel)
+{
+    let mut left = delay;
+    while !left.is_zero() && !cancel.is_cancelled()
+    {
+        let step = left.min(BACKOFF_STEP);
+        sleep(step).await;
+        left -= step;
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn sleep(duration: Duration)
+{
+    std::thread::sleep(duration);
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn sleep(duration: Duration)
+{
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        if let Some(window) = web_sys::window()
+        {
+            let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, duration.as_millis() as i32);
+        }
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests
+{
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::thread::{self, JoinHandle};
+    use std::time::Instant;
+    use crate::fetch::{FetchJob, FetchOptions};
+    use crate::guide::{StudyGuide, TopicStatus};
+    use crate::rank::Context;
+
+    const RESULTS: &str = r#"{"query":{"search":[{"title":"Mercury (planet)"},{"title":"Mercury (element)"}]}}"#;
+
+    /// Serves `responses` as `(status, extra headers, body)` to one request each, then
+    /// returns how many requests it answered.
+    fn serve(responses: Vec<(u16, &'static str, &'static str)>) -> (String, JoinHandle<usize>)
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let api_url = format!("http://{}/w/api.php", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let mut served = 0;
+            for (status, headers, body) in responses
+            {
+                let Ok((mut stream, _)) = listener.accept() else { break };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|read| read > 0) && line != "\r\n"
+                {
+                    line.clear();
+                }
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+                    status,
+                    body.len(),
+                    headers,
+                    body,
+                );
+                served += 1;
+            }
+            served
+        });
+        (api_url, server)
+    }
+
+    fn retry(max_retries: u32) -> RetryPolicy
+    {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn retries_rate_limited_requests()
+    {
+        let (api_url, server) = serve(vec![
+            (429, "Retry-After: 0\r\n", ""),
+            (200, "", RESULTS),
+        ]);
+        let wiki = Wikipedia::new("en").with_api_url(api_url).with_retry(retry(3));
+        let results = pollster::block_on(wiki.search("Mercury", &LookupOptions::default()));
+        assert_eq!(results, Ok(vec!["Mercury (planet)".to_string(), "Mercury (element)".to_string()]));
+        assert_eq!(server.join().unwrap(), 2);
+    }
+
+    #[test]
+    fn records_the_error_once_retries_run_out()
+    {
+        let (api_url, server) = serve(vec![(503, "", ""); 3]);
+        let wiki = Arc::new(Wikipedia::new("en").with_api_url(api_url).with_retry(retry(2)));
+        let guide = StudyGuide::from_topics(vec!["Mercury".to_string()]);
+        let options = FetchOptions { concurrency: 1, rank: false };
+        let mut job = FetchJob::topics(wiki, guide.entries.iter().cloned().enumerate().collect(), Context::new(&guide), &options);
+        let (i, entry) = job.next_blocking().unwrap();
+        assert_eq!(i, 0);
+        assert_eq!(entry.status, TopicStatus::Failed(SourceError::Status(503).to_string()));
+        assert!(job.next_blocking().is_none());
+        assert_eq!(server.join().unwrap(), 3);
+    }
+
+    #[test]
+    fn retry_after_in_seconds_or_as_a_date()
+    {
+        let date = 784_111_777;
+        assert_eq!(parse_retry_after(" 120 ", date), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT", date - 30), Some(Duration::from_secs(30)));
+        assert_eq!(parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT", date + 30), Some(Duration::ZERO));
+        assert_eq!(http_date("Thu, 29 Feb 2024 00:00:00 GMT"), Some(1_709_164_800));
+        assert_eq!(parse_retry_after("Sunday, 06-Nov-94 08:49:37 GMT", 0), None);
+        assert_eq!(parse_retry_after("Sun Nov  6 08:49:37 1994", 0), None);
+        assert_eq!(parse_retry_after("Sun, 06 Nov 1994 25:49:37 GMT", 0), None);
+        assert_eq!(parse_retry_after("soon", 0), None);
+    }
+
+    #[test]
+    fn cancel_cuts_the_backoff_short()
+    {
+        let (api_url, server) = serve(vec![(429, "Retry-After: 30\r\n", "")]);
+        let wiki = Wikipedia::new("en").with_api_url(api_url).with_retry(retry(3));
+        let options = LookupOptions::default();
+        let cancel = options.cancel.clone();
+        let canceller = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            cancel.cancel();
+        });
+        let started = Instant::now();
+        let results = pollster::block_on(wiki.search("Mercury", &options));
+        assert_eq!(results, Err(SourceError::Cancelled));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        canceller.join().unwrap();
+        assert_eq!(server.join().unwrap(), 1);
+    }
+}
@@ -0,0 +1,97 @@
+/// The proposal closest to `query` if it looks like a fix of its spelling: different once case,
+/// punctuation and a leading "The" are ignored, but only by a few letters.
+///
+/// On a tie the earlier proposal wins.
+pub fn correction<'a>(query: &str, proposals: impl IntoIterator<Item = &'a String>) -> Option<String>
+{
+    let query = normalize(query);
+    let allowed = (query.chars().count() / 4).max(1);
+    let mut best: Option<(usize, &String)> = None;
+    for proposal in proposals
+    {
+        let distance = distance(&query, &normalize(proposal));
+        if distance > 0 && distance <= allowed && best.is_none_or(|(best, _)| distance < best)
+        {
+            best = Some((distance, proposal));
+        }
+    }
+    best.map(|(_, proposal)| proposal.clone())
+}
+
+/// Whether `title` is `query` written another way, e.g. with different case or punctuation.
+pub fn same(query: &str, title: &str) -> bool
+{
+    normalize(query) == normalize(title)
+}
+
+fn normalize(text: &str) -> String
+{
+    let text = text.to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect::<String>();
+    let words = text.split_whitespace().collect::<Vec<_>>();
+    let words = match words.split_first()
+    {
+        Some((&"the", rest)) if !rest.is_empty() => rest,
+        _ => &words,
+    };
+    words.join(" ")
+}
+
+/// Levenshtein distance, counting characters.
+fn distance(a: &str, b: &str) -> usize
+{
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate()
+    {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate()
+        {
+            let above = row[j + 1];
+            row[j + 1] = if a == *b { diagonal } else { 1 + diagonal.min(above).min(row[j]) };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn titles(titles: &[&str]) -> Vec<String>
+    {
+        titles.iter().map(|title| title.to_string()).collect()
+    }
+
+    #[test]
+    fn corrects_a_misspelled_topic()
+    {
+        let proposals = titles(&["Hundred Years' War", "Thirty Years' War", "Hundred Days"]);
+        assert_eq!(correction("The Hundreds Year War", &proposals), Some("Hundred Years' War".to_string()));
+    }
+
+    #[test]
+    fn ignores_case_apostrophes_and_a_leading_the()
+    {
+        assert!(same("hundred years war", "The Hundred Years' War"));
+        assert!(same("The Beatles", "the beatles"));
+        assert!(!same("The", "the end"));
+        assert_eq!(correction("hundred years war", &titles(&["Hundred Years' War"])), None);
+    }
+
+    #[test]
+    fn only_proposes_close_spellings()
+    {
+        // A quarter of the letters may differ, and always at least one.
+        assert_eq!(correction("Meiossis", &titles(&["Meiosis"])), Some("Meiosis".to_string()));
+        assert_eq!(correction("Cel", &titles(&["Cell"])), Some("Cell".to_string()));
+        assert_eq!(correction("Cel", &titles(&["Gene"])), None);
+        assert_eq!(correction("Mitosis", &titles(&["Meiosis"])), None);
+        assert_eq!(correction("Mitosis", &titles(&["Mytosys", "Mitosys"])), Some("Mitosys".to_string()));
+    }
+}
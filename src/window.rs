@@ -1,242 +1,224 @@
-use std::fs;
-use std::io::{Read, Seek, Write};
-use std::path::PathBuf;
-use eframe::Frame;
-use egui::{Context};
-use rfd::FileDialog;
-
-pub struct Window
-{
-    topics: Vec<String>,
-    active_topics: Vec<bool>,
-    summaries: Vec<String>,
-    active_summary: i32,
-    topic_choices: Vec<Vec<String>>,
-    chosen_topic: String,
-    output_file_path: PathBuf,
-}
-
-impl Window
-{
-    pub fn new() -> Window
-    {
-        Window {
-            topics: Vec::new(),
-            active_topics: Vec::new(),
-            summaries: Vec::new(),
-            active_summary: -1,
-            topic_choices: Vec::new(),
-            chosen_topic: String::new(),
-            output_file_path: PathBuf::new(),
-        }
-    }
-
-    fn create_topics(&mut self, mut file: fs::File)
-    {
-        let mut content = String::new();
-        if file.read_to_string(&mut content).is_ok()
-        {
-            self.topics.clear();
-            self.active_topics.clear();
-            self.summaries.clear();
-            self.active_summary = -1;
-            self.topic_choices.clear();
-            self.chosen_topic.clear();
-            for line in content.lines()
-            {
-                let line = line.trim();
-                if !line.is_empty()
-                {
-                    self.topics.push(line.into());
-                    self.active_topics.push(true);
-                }
-            }
-        }
-    }
-
-    fn learn_topics(&mut self)
-    {
-        let wiki = wikipedia::Wikipedia::<wikipedia::http::default::Client>::default();
-
-        for (i, topic) in self.topics.iter().enumerate()
-        {
-            if self.active_topics[i]
-            {
-                if let Ok(results) = wiki.search(topic.as_str())
-                {
-                    if results.is_empty()
-                    {
-                        self.active_topics[i] = false;
-                        continue;
-                    }
-
-                    let page = wiki.page_from_title(results[0].clone());
-
-                    self.topic_choices.push(results);
-                    if let Ok(summary) = page.get_summary()
-                    {
-                        self.summaries.push(summary);
-                    }
-                }
-            }
-        }
-    }
-
-    fn create_summary(&self, title: String) -> String
-    {
-        let wiki = wikipedia::Wikipedia::<wikipedia::http::default::Client>::default();
-        let page = wiki.page_from_title(title);
-        if let Ok(summary) = page.get_summary()
-        {
-            summary
-        }
-        else
-        {
-            String::new()
-        }
-    }
-
-    fn write_summaries(&self, path: PathBuf)
-    {
-        let mut file = fs::File::options().write(true).create(true).open(path).unwrap();
-        file.set_len(0).unwrap();
-        file.rewind().unwrap();
-        let summary_i = 0usize;
-        for (i, topic) in self.topics.iter().enumerate()
-        {
-            file.write(format!("{}\n", topic).as_bytes()).unwrap();
-            if self.active_topics[i]
-            {
-                file.write(format!("{}\n\n", self.summaries[summary_i]).as_bytes()).unwrap();
-            }
-            else
-            {
-                file.write("\n\n".as_bytes()).unwrap();
-            }
-        }
-    }
-}
-
-impl eframe::App for Window
-{
-    fn update(&mut self, ctx: &Context, _frame: &mut Frame) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                if ui.button("Open File").clicked()
-                {
-                    let files = FileDialog::new()
-                        .add_filter("text", &["txt"])
-                        .set_directory("/")
-                        .pick_file();
-                    if files.is_some()
-                    {
-                        let files = files.unwrap();
-                        if let Ok(file) = fs::File::options()
-                            .read(true)
-                            .create(false)
-                            .create_new(false)
-                            .open(&files)
-                        {
-                            self.output_file_path = files.with_file_name("out.txt");
-                            self.create_topics(file);
-                        }
-                    }
-                }
-                if ui.button("Get Summaries").clicked()
-                {
-                    if !self.topics.is_empty()
-                    {
-                        self.learn_topics();
-                    }
-                }
-                if ui.button("Create File").clicked()
-                {
-                    if !self.summaries.is_empty() && self.summaries.len() == self.active_topics.iter().filter(|x| **x).count()
-                    {
-                        if let Some(path) = FileDialog::new()
-                            .add_filter("text", &["txt"])
-                            .set_file_name("out")
-                            .set_directory("/")
-                            .save_file()
-                        {
-                            self.write_summaries(path);
-                        }
-                    }
-                }
-            });
-
-            ui.add_space(10.0);
-
-            if !self.topics.is_empty()
-            {
-                if self.summaries.is_empty()
-                {
-                    ui.push_id(69, |ui| {
-                        egui::ScrollArea::both().max_width(238.0).show(ui, |ui| {
-                            egui::Grid::new("Topics").show(ui, |ui| {
-                                for (i, topic) in self.topics.iter().enumerate()
-                                {
-                                    ui.checkbox(&mut self.active_topics[i], topic);
-                                    ui.end_row();
-                                }
-                            });
-                        });
-                    });
-                }
-                else
-                {
-                    ui.horizontal_top(|ui| {
-                        let width = ui.push_id(420, |ui| {
-                            egui::ScrollArea::both().max_width(238.0).show(ui, |ui| {
-                                egui::Grid::new("Topics").show(ui, |ui| {
-                                    let mut summary_i = 0usize;
-                                    for (i, topic) in self.topics.iter().enumerate()
-                                    {
-                                        if self.active_topics[i]
-                                        {
-                                            if ui.button(topic).clicked()
-                                            {
-                                                self.active_summary = summary_i as i32;
-                                                self.chosen_topic = self.topic_choices[self.active_summary as usize][0].clone();
-                                            }
-                                            ui.end_row();
-                                            summary_i += 1;
-                                        }
-                                    }
-                                });
-                            });
-                        }).response.rect.width();
-
-                        if width < 238.0
-                        {
-                            ui.add_space(238.0 - width);
-                        }
-
-                        ui.vertical(|ui| {
-                            if self.active_summary != -1
-                            {
-                                egui::ComboBox::from_label("")
-                                    .selected_text(&self.chosen_topic)
-                                    .show_ui(ui, |ui| {
-                                        for choice in &self.topic_choices[self.active_summary as usize]
-                                        {
-                                            if ui.selectable_value(&mut self.chosen_topic, choice.clone(), choice).clicked()
-                                            {
-                                                self.summaries[self.active_summary as usize] = self.create_summary(self.chosen_topic.clone());
-                                            }
-                                        }
-                                    }
-                                );
-
-                                egui::ScrollArea::vertical().show(ui, |ui|{
-                                    ui.add(egui::Label::new(&self.summaries[self.active_summary as usize]).wrap());
-                                });
-                            }
-                        });
-                    });
-                }
-            }
-        });
-
-    }
-}
\ No newline at end of file
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use eframe::Frame;
+use egui::{Context};
+use rfd::FileDialog;
+use study_guide_filler::cache::SummaryCache;
+use study_guide_filler::error::Error;
+use study_guide_filler::fill;
+use study_guide_filler::import::InputFormat;
+use study_guide_filler::project::Project;
+use study_guide_filler::source::SummarySource;
+use study_guide_filler::writer;
+use crate::notifications::Notification;
+use crate::workspace::Workspace;
+
+pub struct Window<S>
+{
+    workspace: Workspace<S>,
+    /// Where the input was read from, unless it came out of a project.
+    input_path: Option<PathBuf>,
+    output_file_path: PathBuf,
+    /// Where the project was last opened from or saved to.
+    project_path: Option<PathBuf>,
+}
+
+impl<S> Window<S>
+where
+    S: SummarySource + Send + Sync + 'static,
+{
+    pub fn new(source: S, cache: SummaryCache, storage: Option<&dyn eframe::Storage>) -> Window<S>
+    {
+        Window {
+            workspace: Workspace::new(source, cache, storage),
+            input_path: None,
+            output_file_path: PathBuf::new(),
+            project_path: None,
+        }
+    }
+
+    fn create_topics(&mut self, mut file: fs::File, path: &Path)
+    {
+        let mut content = Vec::new();
+        if let Err(e) = file.read_to_end(&mut content)
+        {
+            self.workspace.notifications.error(format!("Could not read {}: {}", path.display(), e));
+        }
+        else
+        {
+            let extension = path.extension().map(|extension| extension.to_string_lossy().to_string());
+            let title = path.file_stem().map(|stem| stem.to_string_lossy().to_string());
+            self.workspace.create_topics(content, extension.as_deref(), title);
+            self.input_path = Some(path.to_path_buf());
+            self.project_path = None;
+        }
+    }
+
+    fn load_project(&mut self, path: PathBuf)
+    {
+        let project = fs::read_to_string(&path)
+            .map_err(Error::from)
+            .and_then(|content| Ok(Project::from_json(&content)?));
+        match project
+        {
+            Ok(project) => {
+                self.workspace.load_project(project);
+                self.input_path = None;
+                self.output_file_path = path.with_file_name("out.txt");
+                self.project_path = Some(path);
+            },
+            Err(e) => self.workspace.notifications.error(format!("Could not open {}: {}", path.display(), e)),
+        }
+    }
+
+    fn save_project(&mut self, path: PathBuf)
+    {
+        let result = self.workspace.project().to_json().map_err(Error::from).and_then(|session| {
+            writer::write_file(&path, session.as_bytes())?;
+            Ok(session)
+        });
+        self.workspace.notifications.push(Notification::saved(path.display(), result.as_ref().map(|_| ())));
+        if let Ok(session) = result
+        {
+            self.project_path = Some(path);
+            self.workspace.saved_session = session;
+        }
+    }
+
+    fn save_project_as(&mut self)
+    {
+        if let Some(path) = FileDialog::new()
+            .add_filter("Project", &["json"])
+            .set_file_name(self.workspace.project().file_name())
+            .set_directory(self.output_file_path.parent().unwrap_or(Path::new("/")))
+            .save_file()
+        {
+            self.save_project(path);
+        }
+    }
+
+    /// Writes the input back to the file it was read from after a topic in it was corrected.
+    fn write_input(&mut self)
+    {
+        if let Some(path) = &self.input_path
+        {
+            let result = writer::write_file(path, &self.workspace.input);
+            self.workspace.notifications.push(Notification::saved(path.display(), result));
+        }
+    }
+
+    fn write_summaries(&mut self, path: PathBuf)
+    {
+        let result = self.workspace.export().and_then(|content| writer::write_file(&path, &content));
+        self.workspace.notifications.push(Notification::saved(path.display(), result));
+    }
+
+    /// Writes a copy of the opened document with the summaries filled in under its topics.
+    fn fill_original(&mut self, path: PathBuf)
+    {
+        let workspace = &mut self.workspace;
+        let result = fill::fill(workspace.input_format, &workspace.input, &workspace.guide)
+            .and_then(|content| writer::write_file(&path, &content));
+        workspace.notifications.push(Notification::saved(path.display(), result));
+    }
+}
+
+impl<S> eframe::App for Window<S>
+where
+    S: SummarySource + Send + Sync + 'static,
+{
+    fn save(&mut self, storage: &mut dyn eframe::Storage)
+    {
+        self.workspace.autosave(storage);
+    }
+
+    fn update(&mut self, ctx: &Context, _frame: &mut Frame) {
+        self.workspace.poll_fetches(ctx);
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if self.workspace.recovery_ui(ui)
+            {
+                self.input_path = None;
+                self.project_path = None;
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Open File").clicked()
+                {
+                    let files = FileDialog::new()
+                        .add_filter("Topics", &InputFormat::EXTENSIONS)
+                        .add_filter("Project", &["json"])
+                        .set_directory("/")
+                        .pick_file();
+                    if let Some(files) = files
+                    {
+                        if Project::is_project(&files.to_string_lossy())
+                        {
+                            self.load_project(files);
+                        }
+                        else
+                        {
+                            match fs::File::options()
+                                .read(true)
+                                .create(false)
+                                .create_new(false)
+                                .open(&files)
+                            {
+                                Ok(file) => {
+                                    self.output_file_path = files.with_file_name("out.txt");
+                                    self.create_topics(file, &files);
+                                },
+                                Err(e) => self.workspace.notifications.error(format!("Could not open {}: {}", files.display(), e)),
+                            }
+                        }
+                    }
+                }
+                if ui.add_enabled(!self.workspace.guide.is_empty(), egui::Button::new("Save")).clicked()
+                {
+                    match self.project_path.clone()
+                    {
+                        Some(path) => self.save_project(path),
+                        None => self.save_project_as(),
+                    }
+                }
+                if ui.add_enabled(!self.workspace.guide.is_empty(), egui::Button::new("Save As")).clicked()
+                {
+                    self.save_project_as();
+                }
+                self.workspace.fetch_button_ui(ui);
+                if ui.button("Create File").clicked() && self.workspace.guide.is_complete()
+                {
+                    if let Some(path) = FileDialog::new()
+                        .add_filter(self.workspace.format.name(), &[self.workspace.format.extension()])
+                        .set_file_name(format!("out.{}", self.workspace.format.extension()))
+                        .set_directory("/")
+                        .save_file()
+                    {
+                        self.write_summaries(path);
+                    }
+                }
+                if ui.add_enabled(fill::can_fill(self.workspace.input_format), egui::Button::new("Fill Original"))
+                    .on_hover_text("Write the summaries into a copy of the opened document")
+                    .clicked() && self.workspace.guide.is_complete()
+                {
+                    let format = self.workspace.input_format;
+                    if let Some(path) = FileDialog::new()
+                        .add_filter(format.name(), &[format.extension()])
+                        .set_file_name(format!("{}-filled.{}", self.workspace.input_title.as_deref().unwrap_or("out"), format.extension()))
+                        .set_directory(self.output_file_path.parent().unwrap_or(Path::new("/")))
+                        .save_file()
+                    {
+                        self.fill_original(path);
+                    }
+                }
+                self.workspace.options_ui(ui);
+            });
+
+            if self.workspace.panels_ui(ui)
+            {
+                self.write_input();
+            }
+        });
+
+    }
+}
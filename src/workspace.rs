@@ -0,0 +1,822 @@
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+use egui::Context;
+use study_guide_filler::cache::{CachedSource, SummaryCache};
+use study_guide_filler::fetch::{self, FetchJob, FetchOptions, Task};
+use study_guide_filler::fill;
+use study_guide_filler::guide::{StudyGuide, TopicEntry, TopicStatus};
+use study_guide_filler::import::{Column, ImportOptions, InputFormat};
+use study_guide_filler::preview::{self, Candidate};
+use study_guide_filler::project::{Project, ProjectInput};
+use study_guide_filler::rank;
+use study_guide_filler::source::SummarySource;
+use study_guide_filler::writer::{Format, WriteOptions};
+use crate::notifications::{Notification, Notifications};
+
+const DAY: u64 = 24 * 60 * 60;
+const INDENT: f32 = 16.0;
+/// Storage key of the autosaved session.
+const AUTOSAVE_KEY: &str = "session";
+#[cfg(not(target_arch = "wasm32"))]
+const CORRECT_INPUT_HOVER: &str = "Also fix a topic in the opened file when its suggested spelling is accepted";
+#[cfg(target_arch = "wasm32")]
+const CORRECT_INPUT_HOVER: &str = "Also fix a topic in the opened document, as kept in the project and used by Fill Original, when its suggested spelling is accepted";
+
+/// The opened topics and their summaries, shown the same way on the desktop and on the web.
+/// The windows around it add opening and saving files.
+pub struct Workspace<S>
+{
+    pub guide: StudyGuide,
+    pub input: Vec<u8>,
+    pub input_format: InputFormat,
+    pub input_title: Option<String>,
+    import_options: ImportOptions,
+    pub notifications: Notifications,
+    source: Arc<CachedSource<S>>,
+    fetch_options: FetchOptions,
+    /// Fix a topic in the input file too when its suggested spelling is accepted.
+    correct_input: bool,
+    fetch: Option<FetchJob>,
+    /// Fetches the summary of the candidate being previewed.
+    preview_fetch: Option<FetchJob>,
+    active_entry: Option<usize>,
+    /// Details of the active topic's candidates for the picker.
+    candidates: Vec<Candidate>,
+    candidates_fetch: Option<Task<Vec<Candidate>>>,
+    preview_title: Option<String>,
+    /// The active topic as it would be with the previewed article.
+    preview: Option<TopicEntry>,
+    pub format: Format,
+    pub write_options: WriteOptions,
+    /// An autosaved session found at startup, until it is restored or discarded.
+    recovered: Option<Project>,
+    /// The project as last saved or opened, to tell whether there is unsaved work.
+    pub saved_session: String,
+}
+
+impl<S> Workspace<S>
+where
+    S: SummarySource + Send + Sync + 'static,
+{
+    pub fn new(source: S, cache: SummaryCache, storage: Option<&dyn eframe::Storage>) -> Workspace<S>
+    {
+        let recovered = storage
+            .and_then(|storage| storage.get_string(AUTOSAVE_KEY))
+            .and_then(|session| Project::from_json(&session).ok());
+        Workspace {
+            guide: StudyGuide::default(),
+            input: Vec::new(),
+            input_format: InputFormat::default(),
+            input_title: None,
+            import_options: ImportOptions::default(),
+            notifications: Notifications::default(),
+            source: Arc::new(CachedSource::new(source, cache)),
+            fetch_options: FetchOptions::default(),
+            correct_input: false,
+            fetch: None,
+            preview_fetch: None,
+            active_entry: None,
+            candidates: Vec::new(),
+            candidates_fetch: None,
+            preview_title: None,
+            preview: None,
+            format: Format::default(),
+            write_options: WriteOptions::default(),
+            recovered,
+            saved_session: String::new(),
+        }
+    }
+
+    /// Reads the topics out of a newly opened file, named `title` without its extension.
+    pub fn create_topics(&mut self, content: Vec<u8>, extension: Option<&str>, title: Option<String>)
+    {
+        self.input_format = InputFormat::detect(extension, &content);
+        self.input = content;
+        self.input_title = title;
+        self.import_options = ImportOptions::default();
+        self.import_topics();
+    }
+
+    /// The current work, to save as a project file.
+    pub fn project(&self) -> Project
+    {
+        Project {
+            guide: self.guide.clone(),
+            input: (!self.input.is_empty()).then(|| ProjectInput {
+                title: self.input_title.clone(),
+                format: self.input_format,
+                options: self.import_options.clone(),
+                content: self.input.clone(),
+            }),
+            source: Some(self.source.name().to_string()),
+            language: Some(self.source.language().to_string()),
+            format: self.format,
+            write_options: self.write_options.clone(),
+            concurrency: self.fetch_options.concurrency,
+            ..Project::default()
+        }
+    }
+
+    fn open_project(&mut self, project: Project)
+    {
+        let input = project.input.unwrap_or_default();
+        self.fetch = None;
+        self.input = input.content;
+        self.input_format = input.format;
+        self.input_title = input.title;
+        self.import_options = input.options;
+        self.guide = project.guide;
+        self.format = project.format;
+        self.write_options = project.write_options;
+        self.fetch_options.concurrency = project.concurrency.clamp(1, 16);
+        self.active_entry = None;
+        self.discard_preview();
+    }
+
+    /// Opens a project read from a file, which counts as saved until it is changed.
+    pub fn load_project(&mut self, project: Project)
+    {
+        self.open_project(project);
+        self.saved_session = self.project().to_json().unwrap_or_default();
+    }
+
+    /// Autosaves the session until it is saved as a project, so it can be restored after a crash.
+    pub fn autosave(&self, storage: &mut dyn eframe::Storage)
+    {
+        if self.recovered.is_some()
+        {
+            // Not answered yet, keep it for next time.
+            return;
+        }
+        let Ok(session) = self.project().to_json() else { return };
+        let unsaved = !self.guide.is_empty() && session != self.saved_session;
+        storage.set_string(AUTOSAVE_KEY, if unsaved { session } else { String::new() });
+    }
+
+    /// Offers to bring back the session autosaved before the app was last closed or crashed.
+    /// Returns whether it was restored.
+    pub fn recovery_ui(&mut self, ui: &mut egui::Ui) -> bool
+    {
+        let Some(project) = &self.recovered else { return false };
+        let mut restore = None;
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "\"{}\" was not saved last time, with {} topics.",
+                project.guide.title.as_deref().unwrap_or("Untitled"),
+                project.guide.entries.len(),
+            ));
+            if ui.button("Restore").clicked()
+            {
+                restore = Some(true);
+            }
+            if ui.button("Discard").clicked()
+            {
+                restore = Some(false);
+            }
+        });
+        match restore
+        {
+            Some(true) => {
+                if let Some(project) = self.recovered.take()
+                {
+                    self.open_project(project);
+                    return true;
+                }
+            },
+            Some(false) => self.recovered = None,
+            None => (),
+        }
+        false
+    }
+
+    /// Reads the topics out of the opened file again, e.g. after another column was picked.
+    fn import_topics(&mut self)
+    {
+        match self.input_format.import(&self.input, &self.import_options)
+        {
+            Ok(mut guide) => {
+                self.fetch = None;
+                guide.title = guide.title.or_else(|| self.input_title.clone());
+                self.guide = guide;
+                self.active_entry = None;
+                self.discard_preview();
+                for warning in std::mem::take(&mut self.guide.warnings)
+                {
+                    self.notifications.push(Notification::notice(warning));
+                }
+                let unsure = self.guide.entries.iter().filter(|entry| !entry.enabled).count();
+                if unsure > 0 && !self.input_format.is_document()
+                {
+                    self.notifications.push(Notification::notice(format!(
+                        "{} lines that are not list items or headings were left unchecked", unsure,
+                    )));
+                }
+            },
+            Err(e) => self.notifications.error(e),
+        }
+    }
+
+    fn learn_topics(&mut self)
+    {
+        let entries = self.guide.entries.iter()
+            .cloned()
+            .enumerate()
+            .filter(|(_, entry)| entry.enabled)
+            .collect();
+        self.fetch_topics(entries, rank::Context::new(&self.guide));
+    }
+
+    /// Fetches the topics that came back with no results or an error again, keeping the rest.
+    fn retry_failed(&mut self)
+    {
+        let context = rank::Context::new(&self.guide);
+        let entries = self.guide.entries.iter_mut()
+            .enumerate()
+            .filter(|(_, entry)| entry.enabled && entry.status.can_retry())
+            .map(|(i, entry)| {
+                entry.reset();
+                (i, entry.clone())
+            })
+            .collect();
+        self.fetch_topics(entries, context);
+    }
+
+    /// Starts fetching `entries`, crediting the source in the guide.
+    fn fetch_topics(&mut self, entries: Vec<fetch::EntryUpdate>, context: rank::Context)
+    {
+        self.guide.attribution = Some(self.source.attribution());
+        self.fetch = Some(FetchJob::topics(self.source.clone(), entries, context, &self.fetch_options));
+    }
+
+    /// Searches for the suggested spelling of topic `i` instead, fixing the topic in the input
+    /// file too if `correct_input` is set. Returns whether `input` was changed.
+    fn correct_topic(&mut self, i: usize) -> bool
+    {
+        let mut corrected = false;
+        let entry = &mut self.guide.entries[i];
+        let Some(query) = entry.accept_suggestion() else { return false };
+        if let Some(origin) = entry.origin.filter(|_| self.correct_input)
+        {
+            match fill::rename(self.input_format, &self.input, origin, &query, &entry.query)
+            {
+                Ok(input) => {
+                    self.input = input;
+                    corrected = true;
+                },
+                Err(e) => self.notifications.error(format!("Could not correct the input file: {}", e)),
+            }
+        }
+        let entries = vec![(i, self.guide.entries[i].clone())];
+        self.fetch_topics(entries, rank::Context::new(&self.guide));
+        corrected
+    }
+
+    fn is_fetching(&self, i: usize) -> bool
+    {
+        self.fetch.as_ref().is_some_and(|job| job.is_fetching(i))
+    }
+
+    /// Shows topic `i` in the candidate picker and looks up the details of its candidates.
+    fn select_topic(&mut self, i: usize)
+    {
+        self.active_entry = Some(i);
+        self.discard_preview();
+        self.candidates.clear();
+        let source = self.source.clone();
+        let entry = self.guide.entries[i].clone();
+        self.candidates_fetch = Some(Task::spawn(move || async move {
+            preview::candidates(source.as_ref(), &entry).await
+        }));
+    }
+
+    /// Fetches the summary of `title` for topic `i` without replacing the current one.
+    fn preview_candidate(&mut self, i: usize, title: String)
+    {
+        self.preview = None;
+        self.preview_title = Some(title.clone());
+        self.preview_fetch = Some(FetchJob::summary(self.source.clone(), i, self.guide.entries[i].clone(), title));
+    }
+
+    fn discard_preview(&mut self)
+    {
+        self.preview = None;
+        self.preview_title = None;
+        self.preview_fetch = None;
+    }
+
+    /// Replaces topic `i` with the previewed article, or with the articles it lists if it is a
+    /// disambiguation page.
+    fn confirm_preview(&mut self, i: usize)
+    {
+        let Some(entry) = self.preview.take() else { return };
+        let ambiguous = entry.status == TopicStatus::Ambiguous;
+        self.guide.apply(i, entry);
+        self.guide.attribution = Some(self.source.attribution());
+        if ambiguous
+        {
+            self.select_topic(i);
+        }
+        else
+        {
+            self.discard_preview();
+        }
+    }
+
+    pub fn poll_fetches(&mut self, ctx: &Context)
+    {
+        if let Some(session) = self.notifications.take_saved_session()
+        {
+            self.saved_session = session;
+        }
+        if let Some(fetch) = &mut self.fetch
+        {
+            for (i, entry) in fetch.poll()
+            {
+                self.guide.apply(i, entry);
+            }
+            if fetch.is_finished()
+            {
+                self.fetch = None;
+                let _ = self.source.cache().save();
+            }
+            else
+            {
+                ctx.request_repaint_after(Duration::from_millis(100));
+            }
+        }
+        if let Some(fetch) = &mut self.preview_fetch
+        {
+            if let Some((_, entry)) = fetch.poll().pop()
+            {
+                self.preview = Some(entry);
+            }
+            if fetch.is_finished()
+            {
+                self.preview_fetch = None;
+                let _ = self.source.cache().save();
+            }
+            else
+            {
+                ctx.request_repaint_after(Duration::from_millis(100));
+            }
+        }
+        if let Some(task) = &self.candidates_fetch
+        {
+            match task.poll()
+            {
+                Some(candidates) => {
+                    self.candidates = candidates;
+                    self.candidates_fetch = None;
+                },
+                None => ctx.request_repaint_after(Duration::from_millis(100)),
+            }
+        }
+    }
+
+    /// The candidates of topic `i` with what is known about them, its current summary, and a
+    /// preview of the candidate clicked, which only replaces the summary once confirmed.
+    fn picker_ui(&mut self, ui: &mut egui::Ui, i: usize)
+    {
+        let mut clicked = None;
+        let mut confirmed = false;
+        let mut discarded = false;
+        let entry = &self.guide.entries[i];
+        ui.columns(3, |columns| {
+            egui::ScrollArea::vertical().id_salt("Candidates").show(&mut columns[0], |ui| {
+                for title in &entry.candidates
+                {
+                    let label = if entry.chosen_title.as_ref() == Some(title) { format!("✔ {}", title) } else { title.clone() };
+                    if ui.selectable_label(self.preview_title.as_ref() == Some(title), egui::RichText::new(label).strong()).clicked()
+                    {
+                        clicked = Some(title.clone());
+                    }
+                    if let Some(candidate) = self.candidates.iter().find(|candidate| &candidate.title == title)
+                    {
+                        if let Some(description) = &candidate.description
+                        {
+                            ui.add(egui::Label::new(description).wrap());
+                        }
+                        if let Some(snippet) = &candidate.snippet
+                        {
+                            ui.add(egui::Label::new(egui::RichText::new(snippet).small().weak()).wrap());
+                        }
+                        if let Some(size) = candidate.size()
+                        {
+                            ui.weak(size);
+                        }
+                    }
+                    ui.separator();
+                }
+                if self.candidates_fetch.is_some()
+                {
+                    ui.spinner();
+                }
+            });
+
+            egui::ScrollArea::vertical().id_salt("Current").show(&mut columns[1], |ui| {
+                ui.strong(entry.chosen_title.as_deref().unwrap_or("No article chosen"));
+                summary_ui(ui, entry);
+            });
+
+            egui::ScrollArea::vertical().id_salt("Preview").show(&mut columns[2], |ui| {
+                match (&self.preview_title, &self.preview)
+                {
+                    (Some(title), Some(preview)) => {
+                        ui.strong(format!("Preview: {}", title));
+                        ui.horizontal(|ui| {
+                            let ambiguous = preview.status == TopicStatus::Ambiguous;
+                            let label = if ambiguous { "Use Its Articles" } else { "Use This Article" };
+                            confirmed = ui.add_enabled(preview.is_found() || ambiguous, egui::Button::new(label)).clicked();
+                            discarded = ui.button("Discard").clicked();
+                        });
+                        summary_ui(ui, preview);
+                    },
+                    (Some(title), None) => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label(format!("Loading {}", title));
+                        });
+                    },
+                    (None, _) => {
+                        ui.weak("Click a candidate to preview its summary here");
+                    },
+                }
+            });
+        });
+
+        if let Some(title) = clicked
+        {
+            self.preview_candidate(i, title);
+        }
+        if confirmed
+        {
+            self.confirm_preview(i);
+        }
+        if discarded
+        {
+            self.discard_preview();
+        }
+    }
+
+    fn cache_ui(&mut self, ui: &mut egui::Ui)
+    {
+        ui.collapsing("Cache", |ui| {
+            ui.horizontal(|ui| {
+                let mut offline = self.source.is_offline();
+                if ui.checkbox(&mut offline, "Offline only").changed()
+                {
+                    self.source.set_offline(offline);
+                }
+
+                let mut cache = self.source.cache();
+                let mut days = cache.expiry.as_secs() / DAY;
+                if ui.add(egui::DragValue::new(&mut days).range(0..=365).prefix("Expire after ").suffix(" days")).changed()
+                {
+                    cache.expiry = Duration::from_secs(days * DAY);
+                }
+                ui.label(format!("{} cached, {} stale", cache.len(), cache.stale_count()));
+                if ui.button("Purge stale").clicked()
+                {
+                    cache.purge_stale();
+                    let _ = cache.save();
+                }
+                drop(cache);
+
+                if ui.add_enabled(!self.source.is_offline(), egui::Button::new("Refresh stale")).clicked()
+                {
+                    let source = self.source.clone();
+                    fetch::spawn_task(move || async move {
+                        source.refresh_stale().await;
+                        let _ = source.cache().save();
+                    });
+                }
+            });
+        });
+    }
+
+    fn import_ui(&mut self, ui: &mut egui::Ui)
+    {
+        if self.input_format.is_document()
+        {
+            ui.horizontal(|ui| {
+                let checked = self.guide.entries.iter().filter(|entry| entry.enabled).count();
+                ui.label(format!(
+                    "Found {} possible topics in the {}, {} checked. Confirm which to summarize.",
+                    self.guide.entries.len(), self.input_format.name(), checked,
+                ));
+                for (label, enabled) in [("All", true), ("None", false)]
+                {
+                    if ui.button(label).clicked()
+                    {
+                        self.guide.entries.iter_mut().for_each(|entry| entry.enabled = enabled);
+                    }
+                }
+            });
+            return;
+        }
+
+        let columns = self.input_format.columns(&self.input);
+        let mut options = self.import_options.clone();
+        ui.horizontal(|ui| {
+            ui.label(format!("{} columns:", self.input_format.name()));
+            column_ui(ui, "Topics", &columns, &mut options.column);
+            column_ui(ui, "Notes", &columns, &mut options.notes_column);
+        });
+        if options != self.import_options
+        {
+            self.import_options = options;
+            self.import_topics();
+        }
+    }
+
+    fn topics_ui(&mut self, ui: &mut egui::Ui)
+    {
+        egui::Grid::new("Topics").show(ui, |ui| {
+            for i in 0..self.guide.entries.len()
+            {
+                let section = self.guide.entries[i].section;
+                if let Some(title) = self.guide.section_title(&self.guide.entries[i]).filter(|_| self.guide.starts_section(i))
+                {
+                    let title = egui::RichText::new(title).strong();
+                    let total = self.guide.entries.iter().filter(|entry| entry.section == section).count();
+                    let enabled = self.guide.entries.iter().filter(|entry| entry.section == section && entry.enabled).count();
+                    let mut all = enabled == total;
+                    if ui.add(egui::Checkbox::new(&mut all, title).indeterminate(enabled > 0 && enabled < total)).changed()
+                    {
+                        for entry in self.guide.entries.iter_mut().filter(|entry| entry.section == section)
+                        {
+                            entry.enabled = all;
+                        }
+                    }
+                    ui.end_row();
+                }
+                else if i > 0 && self.guide.starts_section(i)
+                {
+                    ui.separator();
+                    ui.end_row();
+                }
+
+                let indent = indent(&self.guide, &self.guide.entries[i]);
+                let entry = &mut self.guide.entries[i];
+                ui.horizontal(|ui| {
+                    ui.add_space(indent);
+                    let checkbox = ui.checkbox(&mut entry.enabled, &entry.query);
+                    if let Some(directives) = entry.directives()
+                    {
+                        checkbox.on_hover_text(directives);
+                    }
+                });
+                ui.end_row();
+            }
+        });
+    }
+
+    fn format_ui(&mut self, ui: &mut egui::Ui)
+    {
+        egui::ComboBox::from_id_salt("Format")
+            .selected_text(self.format.name())
+            .show_ui(ui, |ui| {
+                for format in Format::ALL
+                {
+                    ui.selectable_value(&mut self.format, format, format.name());
+                }
+            });
+        if self.format.has_options()
+        {
+            ui.checkbox(&mut self.write_options.table_of_contents, "Contents");
+            ui.checkbox(&mut self.write_options.footer, "Footer");
+        }
+    }
+
+    fn layout_ui(&mut self, ui: &mut egui::Ui)
+    {
+        let layout = &mut self.write_options.layout;
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut layout.font_size).range(6.0..=24.0).speed(0.5).prefix("Font size ").suffix(" pt"));
+            ui.add(egui::DragValue::new(&mut layout.margin).range(0.0..=50.0).prefix("Margin ").suffix(" mm"));
+            ui.add(egui::DragValue::new(&mut layout.columns).range(1..=2).prefix("Columns: "));
+            ui.checkbox(&mut layout.title_page, "Title page");
+        });
+    }
+
+    fn cancel_fetch(&mut self)
+    {
+        if let Some(mut fetch) = self.fetch.take()
+        {
+            fetch.cancel();
+            for (i, entry) in fetch.poll()
+            {
+                self.guide.apply(i, entry);
+            }
+            self.guide.cancel_pending();
+        }
+    }
+
+    /// The guide in the chosen output format, warning about anything that format loses.
+    pub fn export(&mut self) -> io::Result<Vec<u8>>
+    {
+        if let Some(warning) = self.format.warning(&self.guide)
+        {
+            self.notifications.push(Notification::notice(warning));
+        }
+        self.format.to_bytes(&self.guide, &self.write_options)
+    }
+
+    /// The "Get Summaries" button, between the windows' own file buttons.
+    pub fn fetch_button_ui(&mut self, ui: &mut egui::Ui)
+    {
+        if ui.add_enabled(self.fetch.is_none(), egui::Button::new("Get Summaries")).clicked() && !self.guide.is_empty()
+        {
+            self.guide.clear_results();
+            self.active_entry = None;
+            self.discard_preview();
+            self.learn_topics();
+        }
+    }
+
+    /// The output format and fetch settings at the end of the toolbar.
+    pub fn options_ui(&mut self, ui: &mut egui::Ui)
+    {
+        self.format_ui(ui);
+        ui.add(egui::DragValue::new(&mut self.fetch_options.concurrency).range(1..=16).prefix("Parallel: "));
+        ui.checkbox(&mut self.fetch_options.rank, "Rank")
+            .on_hover_text("Pick the search result that fits the other topics best instead of the first one");
+    }
+
+    /// Everything under the toolbar. Returns whether a topic was corrected in `input`, for the
+    /// window to write it back.
+    pub fn panels_ui(&mut self, ui: &mut egui::Ui) -> bool
+    {
+        if self.format.is_paginated()
+        {
+            self.layout_ui(ui);
+        }
+        if (self.input_format.is_table() || self.input_format.is_document()) && !self.guide.has_results()
+        {
+            self.import_ui(ui);
+        }
+        self.notifications.ui(ui);
+
+        let mut cancel = false;
+        if let Some(fetch) = &self.fetch
+        {
+            ui.horizontal(|ui| {
+                ui.add(egui::ProgressBar::new(fetch.progress())
+                    .desired_width(238.0)
+                    .text(format!("{} of {} done", fetch.done(), fetch.total())));
+                cancel = ui.button("Cancel").clicked();
+            });
+        }
+        if cancel
+        {
+            self.cancel_fetch();
+        }
+
+        self.cache_ui(ui);
+
+        ui.add_space(10.0);
+
+        if self.guide.is_empty()
+        {
+            return false;
+        }
+        if !self.guide.has_results()
+        {
+            ui.push_id(69, |ui| {
+                egui::ScrollArea::both().max_width(238.0).show(ui, |ui| {
+                    self.topics_ui(ui);
+                });
+            });
+            return false;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(self.guide.status_summary());
+            let failed = self.guide.entries.iter().filter(|entry| entry.enabled && entry.status.can_retry()).count();
+            if ui.add_enabled(self.fetch.is_none() && failed > 0, egui::Button::new("Retry Failed"))
+                .on_hover_text("Fetch the topics with no results, an error or a cancelled fetch again")
+                .clicked()
+            {
+                self.retry_failed();
+            }
+            ui.add_enabled(fill::can_fill(self.input_format), egui::Checkbox::new(&mut self.correct_input, "Correct the input file"))
+                .on_hover_text(CORRECT_INPUT_HOVER);
+        });
+        let mut selected = None;
+        let mut corrected = None;
+        ui.horizontal_top(|ui| {
+            let width = ui.push_id(420, |ui| {
+                egui::ScrollArea::both().max_width(238.0).show(ui, |ui| {
+                    egui::Grid::new("Topics").show(ui, |ui| {
+                        for (i, entry) in self.guide.entries.iter().enumerate()
+                        {
+                            if let Some(title) = self.guide.section_title(entry).filter(|_| self.guide.starts_section(i))
+                            {
+                                ui.strong(title);
+                                ui.end_row();
+                            }
+                            if entry.enabled
+                            {
+                                let button = ui.horizontal(|ui| {
+                                    ui.add_space(indent(&self.guide, entry));
+                                    ui.add_enabled(!entry.candidates.is_empty(), egui::Button::new(&entry.query))
+                                        .on_disabled_hover_text(entry.status.to_string())
+                                }).inner;
+                                status_ui(ui, entry, self.is_fetching(i));
+                                if let Some(suggestion) = &entry.suggestion
+                                {
+                                    if ui.add_enabled(self.fetch.is_none(), egui::Button::new(format!("{}?", suggestion)).small())
+                                        .on_hover_text(format!("Did you mean \"{}\"? Search for it instead", suggestion))
+                                        .clicked()
+                                    {
+                                        corrected = Some(i);
+                                    }
+                                }
+                                if button.clicked()
+                                {
+                                    selected = Some(i);
+                                }
+                                ui.end_row();
+                            }
+                        }
+                    });
+                });
+            }).response.rect.width();
+
+            if width < 238.0
+            {
+                ui.add_space(238.0 - width);
+            }
+
+            ui.vertical(|ui| {
+                if let Some(i) = self.active_entry
+                {
+                    self.picker_ui(ui, i);
+                }
+            });
+        });
+        if let Some(i) = selected
+        {
+            self.select_topic(i);
+        }
+        corrected.is_some_and(|i| self.correct_topic(i))
+    }
+}
+
+fn status_ui(ui: &mut egui::Ui, entry: &TopicEntry, fetching: bool)
+{
+    let status = &entry.status;
+    let visuals = ui.visuals();
+    let (label, color) = match status
+    {
+        _ if fetching => ("fetching", visuals.strong_text_color()),
+        TopicStatus::Pending | TopicStatus::Cancelled => (status.label(), visuals.weak_text_color()),
+        TopicStatus::Found if entry.needs_review() => ("review", visuals.warn_fg_color),
+        TopicStatus::Found => (status.label(), visuals.text_color()),
+        TopicStatus::NotFound | TopicStatus::Ambiguous => (status.label(), visuals.warn_fg_color),
+        TopicStatus::Failed(_) => (status.label(), visuals.error_fg_color),
+    };
+    let hover = match entry.confidence
+    {
+        _ if fetching => "Fetching".to_string(),
+        Some(confidence) if entry.is_found() => format!("Picked with {:.0}% confidence", confidence * 100.0),
+        _ => status.to_string(),
+    };
+    ui.colored_label(color, label).on_hover_text(hover);
+}
+
+fn summary_ui(ui: &mut egui::Ui, entry: &TopicEntry)
+{
+    match &entry.summary
+    {
+        Some(summary) => ui.add(egui::Label::new(summary).wrap()),
+        None => ui.label(entry.status.to_string()),
+    };
+}
+
+/// How far a topic's row is pushed right to show its section and nesting.
+fn indent(guide: &StudyGuide, entry: &TopicEntry) -> f32
+{
+    let depth = entry.depth + guide.section_title(entry).is_some() as usize;
+    depth as f32 * INDENT
+}
+
+fn column_ui(ui: &mut egui::Ui, label: &str, columns: &[String], column: &mut Option<Column>)
+{
+    let selected = match column
+    {
+        Some(Column::Index(i)) => columns.get(*i).cloned().unwrap_or_default(),
+        Some(Column::Name(name)) => name.clone(),
+        None => "Automatic".to_string(),
+    };
+    egui::ComboBox::from_label(label)
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(column, None, "Automatic");
+            for (i, name) in columns.iter().enumerate()
+            {
+                ui.selectable_value(column, Some(Column::Index(i)), name);
+            }
+        });
+}
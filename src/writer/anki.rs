@@ -0,0 +1,312 @@
+use std::io::{self, Cursor, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+use rusqlite::{params, Connection, DatabaseName};
+use serde_json::json;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+use crate::guide::StudyGuide;
+use crate::source::Attribution;
+use crate::writer::{self, html};
+
+// Fixed so importing a newer deck updates the note type instead of adding another.
+const MODEL_ID: i64 = 1_700_000_000_001;
+const MODEL_NAME: &str = "Study Guide Filler";
+
+// The legacy schema (version 11) every Anki release can import.
+const SCHEMA: &str = "
+CREATE TABLE col (id integer primary key, crt integer not null, mod integer not null, scm integer not null, ver integer not null, dty integer not null, usn integer not null, ls integer not null, conf text not null, models text not null, decks text not null, dconf text not null, tags text not null);
+CREATE TABLE notes (id integer primary key, guid text not null, mid integer not null, mod integer not null, usn integer not null, tags text not null, flds text not null, sfld integer not null, csum integer not null, flags integer not null, data text not null);
+CREATE TABLE cards (id integer primary key, nid integer not null, did integer not null, ord integer not null, mod integer not null, usn integer not null, type integer not null, queue integer not null, due integer not null, ivl integer not null, factor integer not null, reps integer not null, lapses integer not null, left integer not null, odue integer not null, odid integer not null, flags integer not null, data text not null);
+CREATE TABLE revlog (id integer primary key, cid integer not null, usn integer not null, ease integer not null, ivl integer not null, lastIvl integer not null, factor integer not null, time integer not null, type integer not null);
+CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);
+CREATE INDEX ix_notes_usn on notes (usn);
+CREATE INDEX ix_cards_usn on cards (usn);
+CREATE INDEX ix_revlog_usn on revlog (usn);
+CREATE INDEX ix_cards_nid on cards (nid);
+CREATE INDEX ix_cards_sched on cards (did, queue, due);
+CREATE INDEX ix_revlog_cid on revlog (cid);
+CREATE INDEX ix_notes_csum on notes (csum);
+";
+
+const CSS: &str = ".card {
+    font-family: arial;
+    font-size: 20px;
+    text-align: center;
+    color: black;
+    background-color: white;
+}
+.back {
+    text-align: left;
+    font-size: 16px;
+}
+.notes {
+    font-style: italic;
+    margin-bottom: 1em;
+}
+.source {
+    margin-top: 1em;
+    font-size: 12px;
+    color: #777;
+}";
+
+/// Writes an Anki package with a note for every enabled topic that has a summary.
+pub fn write<W: Write>(guide: &StudyGuide, out: &mut W) -> io::Result<()>
+{
+    let collection = collection(guide).map_err(io::Error::other)?;
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file("collection.anki2", SimpleFileOptions::default()).map_err(io::Error::other)?;
+    zip.write_all(&collection)?;
+    // No pictures or sounds, so the media manifest is empty.
+    zip.start_file("media", SimpleFileOptions::default()).map_err(io::Error::other)?;
+    zip.write_all(b"{}")?;
+    let content = zip.finish().map_err(io::Error::other)?.into_inner();
+
+    out.write_all(&content)?;
+    out.flush()
+}
+
+fn collection(guide: &StudyGuide) -> rusqlite::Result<Vec<u8>>
+{
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let (seconds, millis) = (now.as_secs() as i64, now.as_millis() as i64);
+    let deck_name = writer::title(guide);
+    let deck_id = id(deck_name);
+
+    let connection = Connection::open_in_memory()?;
+    connection.execute_batch(SCHEMA)?;
+
+    let deck = |id: i64, name: &str| json!({
+        "id": id,
+        "name": name,
+        "desc": "",
+        "mod": seconds,
+        "usn": -1,
+        "collapsed": false,
+        "newToday": [0, 0],
+        "revToday": [0, 0],
+        "lrnToday": [0, 0],
+        "timeToday": [0, 0],
+        "dyn": 0,
+        "conf": 1,
+        "extendNew": 10,
+        "extendRev": 50,
+    });
+    let field = |name: &str, ord: u32| json!({
+        "name": name,
+        "ord": ord,
+        "sticky": false,
+        "rtl": false,
+        "font": "Arial",
+        "size": 20,
+        "media": [],
+    });
+    let models = json!({
+        MODEL_ID.to_string(): {
+            "id": MODEL_ID,
+            "name": MODEL_NAME,
+            "type": 0,
+            "mod": seconds,
+            "usn": -1,
+            "sortf": 0,
+            "did": deck_id,
+            "tmpls": [{
+                "name": "Card 1",
+                "ord": 0,
+                "qfmt": "{{Front}}",
+                "afmt": "{{FrontSide}}\n\n<hr id=answer>\n\n<div class=back>{{Back}}</div>",
+                "did": null,
+                "bqfmt": "",
+                "bafmt": "",
+            }],
+            "flds": [field("Front", 0), field("Back", 1)],
+            "css": CSS,
+            "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage[utf8]{inputenc}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+            "latexPost": "\\end{document}",
+            "tags": [],
+            "vers": [],
+            "req": [[0, "any", [0]]],
+        },
+    });
+    let decks = json!({
+        "1": deck(1, "Default"),
+        deck_id.to_string(): deck(deck_id, deck_name),
+    });
+    let deck_config = json!({
+        "1": {
+            "id": 1,
+            "name": "Default",
+            "new": { "delays": [1, 10], "ints": [1, 4, 7], "initialFactor": 2500, "order": 1, "perDay": 20, "bury": false, "separate": true },
+            "rev": { "perDay": 200, "ease4": 1.3, "fuzz": 0.05, "minSpace": 1, "ivlFct": 1, "maxIvl": 36500, "bury": false, "hardFactor": 1.2 },
+            "lapse": { "delays": [10], "mult": 0, "minInt": 1, "leechFails": 8, "leechAction": 0 },
+            "maxTaken": 60,
+            "timer": 0,
+            "autoplay": true,
+            "replayq": true,
+            "mod": 0,
+            "usn": 0,
+            "dyn": false,
+        },
+    });
+    let config = json!({
+        "nextPos": 1,
+        "estTimes": true,
+        "activeDecks": [deck_id],
+        "sortType": "noteFld",
+        "timeLim": 0,
+        "sortBackwards": false,
+        "addToCur": true,
+        "curDeck": deck_id,
+        "newBury": true,
+        "newSpread": 0,
+        "dueCounts": true,
+        "curModel": MODEL_ID.to_string(),
+        "collapseTime": 1200,
+    });
+    connection.execute(
+        "INSERT INTO col VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, ?3, ?4, ?5, ?6, '{}')",
+        params![seconds, millis, config.to_string(), models.to_string(), decks.to_string(), deck_config.to_string()],
+    )?;
+
+    let entries = guide.entries.iter().filter(|entry| entry.enabled && entry.summary.is_some());
+    for (i, entry) in entries.enumerate()
+    {
+        // Anki tags can't hold spaces, and the field stores them space separated with spaces around.
+        let tags = match guide.section_title(entry)
+        {
+            Some(title) => format!(" {} ", title.split_whitespace().collect::<Vec<_>>().join("_")),
+            None => String::new(),
+        };
+        let front = html::escape(&entry.query);
+        let mut back = back(entry.summary.as_deref().unwrap_or_default(), entry.chosen_title.as_deref(), entry.url.as_deref(), guide.attribution.as_ref());
+        if let Some(notes) = &entry.notes
+        {
+            back.insert_str(0, &format!("<div class=notes>{}</div>", html::escape(notes)));
+        }
+        let note_id = millis + i as i64;
+        connection.execute(
+            "INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')",
+            params![
+                note_id,
+                guid(deck_name, &entry.query),
+                MODEL_ID,
+                seconds,
+                tags,
+                format!("{}\x1f{}", front, back),
+                front,
+                checksum(&entry.query),
+            ],
+        )?;
+        connection.execute(
+            "INSERT INTO cards VALUES (?1, ?1, ?2, 0, ?3, -1, 0, 0, ?4, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+            params![note_id, deck_id, seconds, i as i64 + 1],
+        )?;
+    }
+
+    Ok(connection.serialize(DatabaseName::Main)?.to_vec())
+}
+
+fn back(summary: &str, title: Option<&str>, url: Option<&str>, attribution: Option<&Attribution>) -> String
+{
+    let mut back = summary.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(html::escape)
+        .collect::<Vec<_>>()
+        .join("<br><br>");
+    if let (Some(title), Some(url)) = (title, url)
+    {
+        back.push_str(&format!("<div class=source><a href=\"{}\">{}</a>", html::escape(url), html::escape(title)));
+        if let Some(attribution) = attribution
+        {
+            back.push_str(&format!(" &middot; {}, {}", html::escape(&attribution.name), html::escape(&attribution.license)));
+        }
+        back.push_str("</div>");
+    }
+    back
+}
+
+fn sha1(text: &str) -> [u8; 20]
+{
+    sha1_smol::Sha1::from(text).digest().bytes()
+}
+
+/// A positive id that stays the same for the same deck name.
+fn id(name: &str) -> i64
+{
+    let hash = sha1(name);
+    // Keep within the 53 bits JavaScript numbers hold, Anki's desktop and web clients share these ids.
+    (i64::from_be_bytes(hash[..8].try_into().unwrap()) & ((1 << 53) - 1)).max(2)
+}
+
+/// Stable per deck and topic, so importing an updated guide replaces notes instead of duplicating them.
+fn guid(deck: &str, query: &str) -> String
+{
+    const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!#$%&()*+,-./:;<=>?@[]^_`{|}~";
+    let hash = sha1(&format!("{}\x1f{}", deck, query));
+    let mut value = u64::from_be_bytes(hash[..8].try_into().unwrap());
+    let mut guid = String::new();
+    while value > 0
+    {
+        guid.push(CHARS[(value % CHARS.len() as u64) as usize] as char);
+        value /= CHARS.len() as u64;
+    }
+    guid
+}
+
+/// First 8 hex digits of the sort field's SHA-1, which Anki uses to find duplicates.
+fn checksum(field: &str) -> i64
+{
+    let hash = sha1(field);
+    u32::from_be_bytes(hash[..4].try_into().unwrap()) as i64
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::io::Read;
+    use zip::ZipArchive;
+    use crate::source::wikipedia;
+
+    #[test]
+    fn stable_ids()
+    {
+        assert_eq!(id("Biology"), id("Biology"));
+        assert_ne!(guid("Biology", "Mitosis"), guid("Biology", "Meiosis"));
+        assert!((0..1 << 53).contains(&id("Biology")));
+        // The first eight hex digits of the SHA-1, as Anki reads them.
+        assert_eq!(checksum("Mitosis"), i64::from_str_radix(&sha1_smol::Sha1::from("Mitosis").digest().to_string()[..8], 16).unwrap());
+    }
+
+    #[test]
+    fn back_of_the_card()
+    {
+        let attribution = wikipedia::attribution("en");
+        assert_eq!(back("A & B.\n\nC.", None, None, Some(&attribution)), "A &amp; B.<br><br>C.");
+        assert!(back("A.", Some("Mitosis"), Some("https://x"), Some(&attribution)).ends_with("<div class=source><a href=\"https://x\">Mitosis</a> &middot; Wikipedia, CC BY-SA 4.0</div>"));
+        assert!(back("A.", Some("Mitosis"), Some("https://x"), None).ends_with("Mitosis</a></div>"));
+    }
+
+    #[test]
+    fn writes_a_note_per_topic()
+    {
+        let mut guide = StudyGuide::from_topics(vec!["Mitosis".to_string(), "Meiosis".to_string()]);
+        guide.entries[0].summary = Some("Cell division.".to_string());
+        let mut out = Vec::new();
+        write(&guide, &mut out).unwrap();
+
+        let mut archive = ZipArchive::new(Cursor::new(out)).unwrap();
+        let mut collection = Vec::new();
+        archive.by_name("collection.anki2").unwrap().read_to_end(&mut collection).unwrap();
+        let path = std::env::temp_dir().join(format!("study-guide-filler-{}.anki2", std::process::id()));
+        std::fs::write(&path, &collection).unwrap();
+        let connection = Connection::open(&path).unwrap();
+        let fields = connection.query_row("SELECT flds FROM notes", [], |row| row.get::<_, String>(0));
+        let notes = connection.query_row("SELECT count(*) FROM notes", [], |row| row.get::<_, i64>(0)).unwrap();
+        drop(connection);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(notes, 1);
+        assert_eq!(fields.unwrap(), "Mitosis\x1fCell division.");
+    }
+}
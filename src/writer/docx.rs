@@ -0,0 +1,259 @@
+use std::io::{self, Cursor, Write};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+use crate::guide::StudyGuide;
+use crate::writer::{self, WriteOptions};
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+<Default Extension="xml" ContentType="application/xml"/>
+<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>
+<Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>
+<Override PartName="/word/settings.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.settings+xml"/>
+<Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>
+</Types>"#;
+
+const PACKAGE_RELATIONSHIPS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>
+</Relationships>"#;
+
+// Word asks to update fields on open, which fills in the table of contents.
+const SETTINGS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:settings xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:updateFields w:val="true"/>
+</w:settings>"#;
+
+const STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:docDefaults>
+<w:rPrDefault><w:rPr><w:rFonts w:ascii="Calibri" w:hAnsi="Calibri" w:eastAsia="Calibri" w:cs="Calibri"/><w:sz w:val="22"/><w:szCs w:val="22"/><w:lang w:val="en-US"/></w:rPr></w:rPrDefault>
+<w:pPrDefault><w:pPr><w:spacing w:after="160" w:line="264" w:lineRule="auto"/></w:pPr></w:pPrDefault>
+</w:docDefaults>
+<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:qFormat/></w:style>
+<w:style w:type="paragraph" w:styleId="Title"><w:name w:val="Title"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:spacing w:after="240"/></w:pPr><w:rPr><w:rFonts w:ascii="Calibri Light" w:hAnsi="Calibri Light"/><w:sz w:val="56"/><w:szCs w:val="56"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:keepLines/><w:spacing w:before="360" w:after="120"/><w:outlineLvl w:val="0"/></w:pPr><w:rPr><w:rFonts w:ascii="Calibri Light" w:hAnsi="Calibri Light"/><w:color w:val="2F5496"/><w:sz w:val="32"/><w:szCs w:val="32"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:keepLines/><w:spacing w:before="240" w:after="80"/><w:outlineLvl w:val="1"/></w:pPr><w:rPr><w:rFonts w:ascii="Calibri Light" w:hAnsi="Calibri Light"/><w:color w:val="2F5496"/><w:sz w:val="26"/><w:szCs w:val="26"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading3"><w:name w:val="heading 3"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:keepLines/><w:spacing w:before="200" w:after="60"/><w:outlineLvl w:val="2"/></w:pPr><w:rPr><w:rFonts w:ascii="Calibri Light" w:hAnsi="Calibri Light"/><w:color w:val="1F3763"/><w:sz w:val="24"/><w:szCs w:val="24"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading4"><w:name w:val="heading 4"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:keepLines/><w:spacing w:before="160" w:after="40"/><w:outlineLvl w:val="3"/></w:pPr><w:rPr><w:i/><w:color w:val="2F5496"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="TOCHeading"><w:name w:val="TOC Heading"/><w:basedOn w:val="Heading1"/><w:next w:val="Normal"/><w:pPr><w:outlineLvl w:val="9"/></w:pPr></w:style>
+<w:style w:type="paragraph" w:styleId="Source"><w:name w:val="Source"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:rPr><w:i/><w:color w:val="595959"/><w:sz w:val="20"/><w:szCs w:val="20"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Notes"><w:name w:val="Notes"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:pBdr><w:left w:val="single" w:sz="18" w:space="8" w:color="E5A50A"/></w:pBdr><w:ind w:left="284"/></w:pPr><w:rPr><w:i/></w:rPr></w:style>
+<w:style w:type="character" w:styleId="Hyperlink"><w:name w:val="Hyperlink"/><w:rPr><w:color w:val="0563C1"/><w:u w:val="single"/></w:rPr></w:style>
+</w:styles>"#;
+
+const NAMESPACES: &str = r#"xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships""#;
+
+const HYPERLINK: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink";
+
+/// External links, each referenced from the document by its relationship id.
+#[derive(Default)]
+struct Links
+{
+    urls: Vec<String>,
+}
+
+impl Links
+{
+    fn add(&mut self, url: &str) -> String
+    {
+        self.urls.push(url.to_string());
+        // rId1 and rId2 are the styles and settings.
+        format!("rId{}", self.urls.len() + 2)
+    }
+}
+
+pub fn write<W: Write>(guide: &StudyGuide, options: &WriteOptions, out: &mut W) -> io::Result<()>
+{
+    let mut links = Links::default();
+    let body = document(guide, options, &mut links);
+
+    let mut relationships = String::from(r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/settings" Target="settings.xml"/>
+"#);
+    for (i, url) in links.urls.iter().enumerate()
+    {
+        relationships.push_str(&format!(
+            "<Relationship Id=\"rId{}\" Type=\"{}\" Target=\"{}\" TargetMode=\"External\"/>\n",
+            i + 3, HYPERLINK, escape(url),
+        ));
+    }
+    relationships.push_str("</Relationships>");
+
+    let core = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/">
+<dc:title>{}</dc:title>
+<dc:creator>Study Guide Filler</dc:creator>
+</cp:coreProperties>"#,
+        escape(writer::title(guide)),
+    );
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let parts = [
+        ("[Content_Types].xml", CONTENT_TYPES),
+        ("_rels/.rels", PACKAGE_RELATIONSHIPS),
+        ("docProps/core.xml", &core),
+        ("word/_rels/document.xml.rels", &relationships),
+        ("word/document.xml", &body),
+        ("word/styles.xml", STYLES),
+        ("word/settings.xml", SETTINGS),
+    ];
+    for (name, content) in parts
+    {
+        zip.start_file(name, SimpleFileOptions::default()).map_err(io::Error::other)?;
+        zip.write_all(content.as_bytes())?;
+    }
+    let content = zip.finish().map_err(io::Error::other)?.into_inner();
+
+    out.write_all(&content)?;
+    out.flush()
+}
+
+fn document(guide: &StudyGuide, options: &WriteOptions, links: &mut Links) -> String
+{
+    let mut body = String::new();
+    body.push_str(&paragraph(Some("Title"), &run(writer::title(guide))));
+
+    if options.table_of_contents
+    {
+        body.push_str(&paragraph(Some("TOCHeading"), &run("Contents")));
+        body.push_str(&paragraph(None, &format!(
+            "<w:r><w:fldChar w:fldCharType=\"begin\" w:dirty=\"true\"/></w:r>\
+            <w:r><w:instrText xml:space=\"preserve\"> TOC \\o \"1-3\" \\h \\z \\u </w:instrText></w:r>\
+            <w:r><w:fldChar w:fldCharType=\"separate\"/></w:r>\
+            {}\
+            <w:r><w:fldChar w:fldCharType=\"end\"/></w:r>",
+            run("Update the field to fill in the table of contents."),
+        )));
+    }
+
+    for heading in writer::outline(guide)
+    {
+        let style = format!("Heading{}", heading.level.min(4));
+        body.push_str(&paragraph(Some(&style), &run(heading.text)));
+        let Some(entry) = heading.entry.filter(|entry| entry.enabled) else {
+            continue;
+        };
+        if let Some(notes) = &entry.notes
+        {
+            body.push_str(&paragraph(Some("Notes"), &run(notes)));
+        }
+        if let (Some(title), Some(url)) = (&entry.chosen_title, &entry.url)
+        {
+            body.push_str(&paragraph(Some("Source"), &format!("{}{}", run("Article: "), hyperlink(links.add(url), title))));
+        }
+        if let Some(summary) = &entry.summary
+        {
+            for text in summary.lines().map(|line| line.trim()).filter(|line| !line.is_empty())
+            {
+                body.push_str(&paragraph(None, &run(text)));
+            }
+        }
+    }
+
+    if let Some(attribution) = writer::footer(guide, options)
+    {
+        body.push_str(&paragraph(Some("Source"), &format!(
+            "{}{}{}{}{}",
+            run("Summaries from "),
+            hyperlink(links.add(&attribution.url), &attribution.name),
+            run(", available under "),
+            hyperlink(links.add(&attribution.license_url), &attribution.license),
+            run("."),
+        )));
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<w:document {}><w:body>{}\
+        <w:sectPr><w:pgSz w:w=\"12240\" w:h=\"15840\"/><w:pgMar w:top=\"1440\" w:right=\"1440\" w:bottom=\"1440\" w:left=\"1440\" w:header=\"720\" w:footer=\"720\" w:gutter=\"0\"/></w:sectPr>\
+        </w:body></w:document>",
+        NAMESPACES, body,
+    )
+}
+
+fn paragraph(style: Option<&str>, runs: &str) -> String
+{
+    match style
+    {
+        Some(style) => format!("<w:p><w:pPr><w:pStyle w:val=\"{}\"/></w:pPr>{}</w:p>\n", style, runs),
+        None => format!("<w:p>{}</w:p>\n", runs),
+    }
+}
+
+fn run(text: &str) -> String
+{
+    format!("<w:r><w:t xml:space=\"preserve\">{}</w:t></w:r>", escape(text))
+}
+
+fn hyperlink(id: String, text: &str) -> String
+{
+    format!(
+        "<w:hyperlink r:id=\"{}\" w:history=\"1\"><w:r><w:rPr><w:rStyle w:val=\"Hyperlink\"/></w:rPr><w:t xml:space=\"preserve\">{}</w:t></w:r></w:hyperlink>",
+        id, escape(text),
+    )
+}
+
+/// Escapes text for XML and drops the control characters XML 1.0 cannot hold.
+pub fn escape(text: &str) -> String
+{
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars()
+    {
+        match c
+        {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(' '),
+            c if c.is_control() => (),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::io::Read;
+    use zip::ZipArchive;
+
+    #[test]
+    fn escapes_xml()
+    {
+        assert_eq!(escape("a<b> & 'c'\t\u{1}d"), "a&lt;b&gt; &amp; &apos;c&apos; d");
+    }
+
+    #[test]
+    fn writes_a_word_package()
+    {
+        let mut guide = StudyGuide::from_topics(vec!["Mitosis".to_string()]);
+        let entry = &mut guide.entries[0];
+        entry.chosen_title = Some("Mitosis".to_string());
+        entry.url = Some("https://en.wikipedia.org/wiki/Mitosis?a=1&b=2".to_string());
+        entry.summary = Some("Cell division.".to_string());
+        let mut out = Vec::new();
+        write(&guide, &WriteOptions::default(), &mut out).unwrap();
+
+        let mut archive = ZipArchive::new(Cursor::new(out)).unwrap();
+        let mut part = |name: &str| {
+            let mut xml = String::new();
+            archive.by_name(name).unwrap().read_to_string(&mut xml).unwrap();
+            xml
+        };
+        let document = part("word/document.xml");
+        assert!(document.contains("<w:pStyle w:val=\"Heading1\"/></w:pPr><w:r><w:t xml:space=\"preserve\">Mitosis</w:t>"), "{}", document);
+        assert!(document.contains("<w:hyperlink r:id=\"rId3\""), "{}", document);
+        let relationships = part("word/_rels/document.xml.rels");
+        assert!(relationships.contains("Id=\"rId3\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink\" Target=\"https://en.wikipedia.org/wiki/Mitosis?a=1&amp;b=2\""));
+        assert!(part("[Content_Types].xml").contains("/word/document.xml"));
+    }
+}
@@ -0,0 +1,215 @@
+use std::io::{self, Write};
+use crate::guide::StudyGuide;
+use crate::writer::{self, WriteOptions};
+
+const STYLE: &str = "\
+body {
+    max-width: 46em;
+    margin: 2em auto;
+    padding: 0 1em;
+    font: 17px/1.6 Georgia, 'Times New Roman', serif;
+    color: #222;
+    background: #fdfdfb;
+}
+h1, h2 {
+    font-family: 'Segoe UI', Helvetica, Arial, sans-serif;
+    line-height: 1.25;
+}
+h1 {
+    border-bottom: 2px solid #444;
+    padding-bottom: 0.3em;
+}
+h2 {
+    margin-top: 2em;
+}
+a {
+    color: #1a5fb4;
+}
+nav ol {
+    columns: 2;
+    padding-left: 1.5em;
+    list-style: none;
+}
+nav .level-2 {
+    padding-left: 1.2em;
+}
+nav .level-3, nav .level-4, nav .level-5 {
+    padding-left: 2.4em;
+    font-size: 0.95em;
+}
+h2.section {
+    margin-top: 2.5em;
+    border-bottom: 1px solid #ccc;
+}
+.article {
+    font-style: italic;
+    color: #555;
+}
+.notes {
+    border-left: 3px solid #e5a50a;
+    padding-left: 0.8em;
+}
+.missing {
+    color: #a51d2d;
+}
+footer {
+    margin-top: 3em;
+    border-top: 1px solid #ccc;
+    font-size: 0.85em;
+    color: #555;
+}
+@media print {
+    body {
+        max-width: none;
+        margin: 0;
+        font-size: 11pt;
+        background: none;
+    }
+    nav {
+        page-break-after: always;
+    }
+    section {
+        page-break-inside: avoid;
+    }
+    h2 {
+        page-break-after: avoid;
+    }
+    a {
+        color: inherit;
+        text-decoration: none;
+    }
+    .article a::after {
+        content: ' (' attr(href) ')';
+        font-size: 0.8em;
+    }
+}";
+
+pub fn write<W: Write>(guide: &StudyGuide, options: &WriteOptions, out: &mut W) -> io::Result<()>
+{
+    let outline = writer::outline(guide);
+    let title = escape(writer::title(guide));
+
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">")?;
+    writeln!(out, "<title>{}</title>", title)?;
+    writeln!(out, "<style>\n{}\n</style>", STYLE)?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    writeln!(out, "<h1>{}</h1>", title)?;
+
+    if options.table_of_contents
+    {
+        writeln!(out, "<nav>")?;
+        writeln!(out, "<h2>Contents</h2>")?;
+        writeln!(out, "<ol>")?;
+        for heading in &outline
+        {
+            writeln!(out, "<li class=\"level-{}\"><a href=\"#{}\">{}</a></li>", heading.level, escape(&heading.anchor), escape(heading.text))?;
+        }
+        writeln!(out, "</ol>")?;
+        writeln!(out, "</nav>")?;
+    }
+
+    for heading in &outline
+    {
+        let tag = format!("h{}", (heading.level + 1).min(6));
+        let Some(entry) = heading.entry else {
+            writeln!(out, "<{} class=\"section\" id=\"{}\">{}</{}>", tag, escape(&heading.anchor), escape(heading.text), tag)?;
+            continue;
+        };
+        writeln!(out, "<section id=\"{}\">", escape(&heading.anchor))?;
+        writeln!(out, "<{}><a href=\"#{}\">{}</a></{}>", tag, escape(&heading.anchor), escape(heading.text), tag)?;
+        if entry.enabled
+        {
+            if let Some(notes) = &entry.notes
+            {
+                writeln!(out, "<p class=\"notes\">{}</p>", escape(notes))?;
+            }
+            if let (Some(title), Some(url)) = (&entry.chosen_title, &entry.url)
+            {
+                writeln!(out, "<p class=\"article\">Article: <a href=\"{}\">{}</a></p>", escape(url), escape(title))?;
+            }
+            match &entry.summary
+            {
+                Some(summary) => {
+                    for paragraph in summary.lines().map(|line| line.trim()).filter(|line| !line.is_empty())
+                    {
+                        writeln!(out, "<p>{}</p>", escape(paragraph))?;
+                    }
+                },
+                None => writeln!(out, "<p class=\"missing\">{}</p>", escape(&entry.status.to_string()))?,
+            }
+        }
+        writeln!(out, "</section>")?;
+    }
+
+    if let Some(attribution) = writer::footer(guide, options)
+    {
+        writeln!(
+            out,
+            "<footer>Summaries from <a href=\"{}\">{}</a>, available under <a href=\"{}\">{}</a>.</footer>",
+            escape(&attribution.url), escape(&attribution.name), escape(&attribution.license_url), escape(&attribution.license),
+        )?;
+    }
+
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")?;
+    out.flush()
+}
+
+pub fn escape(text: &str) -> String
+{
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars()
+    {
+        match c
+        {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::guide::TopicStatus;
+    use crate::source::wikipedia;
+
+    #[test]
+    fn escapes_text()
+    {
+        assert_eq!(escape("<b> & \"it's\""), "&lt;b&gt; &amp; &quot;it&#39;s&quot;");
+    }
+
+    #[test]
+    fn writes_sections_and_missing_summaries()
+    {
+        let mut guide = StudyGuide::from_topics(vec!["Mitosis".to_string(), "Meiossis".to_string()]);
+        guide.entries[0].summary = Some("Cell <division>.".to_string());
+        guide.entries[1].status = TopicStatus::NotFound;
+        guide.attribution = Some(wikipedia::attribution("en"));
+        let mut out = Vec::new();
+        write(&guide, &WriteOptions::default(), &mut out).unwrap();
+        let html = String::from_utf8(out).unwrap();
+        assert!(html.contains("<title>Study Guide</title>"));
+        assert!(html.contains("<li class=\"level-1\"><a href=\"#mitosis\">Mitosis</a></li>"));
+        assert!(html.contains("<section id=\"mitosis\">\n<h2><a href=\"#mitosis\">Mitosis</a></h2>\n<p>Cell &lt;division&gt;.</p>"), "{}", html);
+        assert!(html.contains("<p class=\"missing\">No results</p>"));
+        assert!(html.contains("<footer>Summaries from <a href=\"https://en.wikipedia.org/\">Wikipedia</a>"));
+
+        guide.attribution = None;
+        let mut out = Vec::new();
+        write(&guide, &WriteOptions::default(), &mut out).unwrap();
+        assert!(!String::from_utf8(out).unwrap().contains("<footer>"));
+    }
+}
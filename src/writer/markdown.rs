@@ -0,0 +1,123 @@
+use std::io::{self, Write};
+use crate::guide::StudyGuide;
+use crate::writer::{self, WriteOptions};
+
+pub fn write<W: Write>(guide: &StudyGuide, options: &WriteOptions, out: &mut W) -> io::Result<()>
+{
+    let outline = writer::outline(guide);
+
+    writeln!(out, "# {}\n", escape(writer::title(guide)))?;
+
+    if options.table_of_contents
+    {
+        writeln!(out, "## Contents\n")?;
+        for heading in &outline
+        {
+            writeln!(out, "{}- [{}](#{})", "  ".repeat(heading.level - 1), escape(heading.text), heading.anchor)?;
+        }
+        writeln!(out)?;
+    }
+
+    for heading in &outline
+    {
+        writeln!(out, "{} {}\n", "#".repeat((heading.level + 1).min(6)), escape(heading.text))?;
+        let Some(entry) = heading.entry.filter(|entry| entry.enabled) else {
+            continue;
+        };
+        if let Some(notes) = &entry.notes
+        {
+            writeln!(out, "> {}\n", escape(notes))?;
+        }
+        if let (Some(title), Some(url)) = (&entry.chosen_title, &entry.url)
+        {
+            writeln!(out, "*Article: [{}](<{}>)*\n", escape(title), url)?;
+        }
+        match &entry.summary
+        {
+            Some(summary) => {
+                for paragraph in summary.lines().map(|line| line.trim()).filter(|line| !line.is_empty())
+                {
+                    writeln!(out, "{}\n", escape(paragraph))?;
+                }
+            },
+            None => writeln!(out, "*{}*\n", escape(&entry.status.to_string()))?,
+        }
+    }
+
+    if let Some(attribution) = writer::footer(guide, options)
+    {
+        writeln!(out, "---\n")?;
+        writeln!(
+            out,
+            "*Summaries from [{}](<{}>), available under [{}](<{}>).*",
+            escape(&attribution.name), attribution.url, escape(&attribution.license), attribution.license_url,
+        )?;
+    }
+    out.flush()
+}
+
+/// Backslash-escapes characters Markdown would otherwise treat as formatting.
+pub fn escape(text: &str) -> String
+{
+    let mut escaped = String::with_capacity(text.len());
+    for (i, c) in text.chars().enumerate()
+    {
+        let line_start = i == 0 && matches!(c, '#' | '-' | '+' | '>' | '=');
+        if line_start || matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|')
+        {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::guide::TopicStatus;
+    use crate::source::wikipedia;
+
+    #[test]
+    fn escapes_formatting()
+    {
+        assert_eq!(escape("# not_a *heading* [x]"), "\\# not\\_a \\*heading\\* \\[x\\]");
+        assert_eq!(escape("a - b"), "a - b");
+    }
+
+    #[test]
+    fn writes_contents_and_summaries()
+    {
+        let mut guide = StudyGuide::from_topics(vec!["Mitosis".to_string()]);
+        guide.title = Some("Biology".to_string());
+        guide.attribution = Some(wikipedia::attribution("en"));
+        let entry = &mut guide.entries[0];
+        entry.chosen_title = Some("Mitosis".to_string());
+        entry.url = Some("https://en.wikipedia.org/wiki/Mitosis".to_string());
+        entry.summary = Some("Cell division.\n\nIn *eukaryotes*.".to_string());
+        let mut out = Vec::new();
+        write(&guide, &WriteOptions::default(), &mut out).unwrap();
+        let markdown = String::from_utf8(out).unwrap();
+        assert!(markdown.starts_with("# Biology\n\n## Contents\n\n- [Mitosis](#mitosis)\n\n## Mitosis\n\n"), "{}", markdown);
+        assert!(markdown.contains("*Article: [Mitosis](<https://en.wikipedia.org/wiki/Mitosis>)*\n\nCell division.\n\nIn \\*eukaryotes\\*.\n\n---"), "{}", markdown);
+        assert!(markdown.ends_with("*Summaries from [Wikipedia](<https://en.wikipedia.org/>), available under [CC BY-SA 4.0](<https://creativecommons.org/licenses/by-sa/4.0/>).*\n"), "{}", markdown);
+
+        let options = WriteOptions { table_of_contents: false, footer: false, ..WriteOptions::default() };
+        let mut out = Vec::new();
+        write(&guide, &options, &mut out).unwrap();
+        let markdown = String::from_utf8(out).unwrap();
+        assert!(!markdown.contains("Contents") && !markdown.contains("---"), "{}", markdown);
+    }
+
+    #[test]
+    fn writes_the_status_of_topics_without_a_summary()
+    {
+        let mut guide = StudyGuide::from_topics(vec!["Mitosis".to_string(), "Meiosis".to_string()]);
+        guide.entries[1].status = TopicStatus::NotFound;
+        let mut out = Vec::new();
+        write(&guide, &WriteOptions { table_of_contents: false, ..WriteOptions::default() }, &mut out).unwrap();
+        let markdown = String::from_utf8(out).unwrap();
+        assert!(markdown.contains("## Mitosis\n\n*Pending*\n\n## Meiosis\n\n*No results*\n"), "{}", markdown);
+    }
+}
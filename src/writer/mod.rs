@@ -0,0 +1,318 @@
+use std::io::{self, Write};
+use serde::{Deserialize, Serialize};
+use crate::guide::{StudyGuide, TopicEntry};
+use crate::source::Attribution;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod anki;
+pub mod docx;
+pub mod html;
+pub mod markdown;
+pub mod pdf;
+pub mod text;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format
+{
+    #[default]
+    Text,
+    Markdown,
+    Html,
+    Pdf,
+    Docx,
+    /// Flashcards, needs SQLite so only on native.
+    #[cfg(not(target_arch = "wasm32"))]
+    Anki,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WriteOptions
+{
+    pub table_of_contents: bool,
+    pub footer: bool,
+    pub layout: PageLayout,
+}
+
+impl Default for WriteOptions
+{
+    fn default() -> WriteOptions
+    {
+        WriteOptions {
+            table_of_contents: true,
+            footer: true,
+            layout: PageLayout::default(),
+        }
+    }
+}
+
+/// Page setup for the paginated formats.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PageLayout
+{
+    /// Body text size in points, headings are scaled from it.
+    pub font_size: f32,
+    /// Margin on every side in millimetres.
+    pub margin: f32,
+    pub columns: usize,
+    pub title_page: bool,
+}
+
+impl Default for PageLayout
+{
+    fn default() -> PageLayout
+    {
+        PageLayout {
+            font_size: 11.0,
+            margin: 20.0,
+            columns: 1,
+            title_page: true,
+        }
+    }
+}
+
+impl Format
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    pub const ALL: [Format; 6] = [Format::Text, Format::Markdown, Format::Html, Format::Pdf, Format::Docx, Format::Anki];
+    #[cfg(target_arch = "wasm32")]
+    pub const ALL: [Format; 5] = [Format::Text, Format::Markdown, Format::Html, Format::Pdf, Format::Docx];
+
+    pub fn from_name(name: &str) -> Option<Format>
+    {
+        match name.to_lowercase().as_str()
+        {
+            "text" | "txt" | "plain" => Some(Format::Text),
+            "markdown" | "md" => Some(Format::Markdown),
+            "html" | "htm" => Some(Format::Html),
+            "pdf" => Some(Format::Pdf),
+            "docx" | "word" => Some(Format::Docx),
+            #[cfg(not(target_arch = "wasm32"))]
+            "anki" | "apkg" => Some(Format::Anki),
+            _ => None,
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Format>
+    {
+        match extension.to_lowercase().as_str()
+        {
+            "markdown" => Some(Format::Markdown),
+            "htm" => Some(Format::Html),
+            extension => Format::ALL.into_iter().find(|format| format.extension() == extension),
+        }
+    }
+
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            Format::Text => "Plain text",
+            Format::Markdown => "Markdown",
+            Format::Html => "HTML",
+            Format::Pdf => "PDF",
+            Format::Docx => "Word",
+            #[cfg(not(target_arch = "wasm32"))]
+            Format::Anki => "Anki deck",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str
+    {
+        match self
+        {
+            Format::Text => "txt",
+            Format::Markdown => "md",
+            Format::Html => "html",
+            Format::Pdf => "pdf",
+            Format::Docx => "docx",
+            #[cfg(not(target_arch = "wasm32"))]
+            Format::Anki => "apkg",
+        }
+    }
+
+    /// Whether the format has a table of contents and footer that `WriteOptions` can turn off.
+    pub fn has_options(&self) -> bool
+    {
+        matches!(self, Format::Markdown | Format::Html | Format::Pdf | Format::Docx)
+    }
+
+    /// Whether the format is laid out on pages using `WriteOptions::layout`.
+    pub fn is_paginated(&self) -> bool
+    {
+        *self == Format::Pdf
+    }
+
+    pub fn write<W: Write>(&self, guide: &StudyGuide, options: &WriteOptions, out: &mut W) -> io::Result<()>
+    {
+        match self
+        {
+            Format::Text => text::write(guide, out),
+            Format::Markdown => markdown::write(guide, options, out),
+            Format::Html => html::write(guide, options, out),
+            Format::Pdf => pdf::write(guide, options, out),
+            Format::Docx => docx::write(guide, options, out),
+            #[cfg(not(target_arch = "wasm32"))]
+            Format::Anki => anki::write(guide, out),
+        }
+    }
+
+    /// What of the guide won't come out right in this format, to tell the user before writing it.
+    pub fn warning(&self, guide: &StudyGuide) -> Option<String>
+    {
+        match self
+        {
+            Format::Pdf => pdf::warning(guide),
+            _ => None,
+        }
+    }
+
+    pub fn to_bytes(&self, guide: &StudyGuide, options: &WriteOptions) -> io::Result<Vec<u8>>
+    {
+        let mut content = Vec::<u8>::new();
+        self.write(guide, options, &mut content)?;
+        Ok(content)
+    }
+}
+
+/// Writes `content` to a temporary file next to `path` and then renames it over `path`,
+/// so a failed write never leaves a half-written file behind.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_file(path: &std::path::Path, content: &[u8]) -> io::Result<()>
+{
+    let name = path.file_name().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file path"))?;
+    let temp = path.with_file_name(format!(".{}.tmp", name.to_string_lossy()));
+    let result = std::fs::File::create(&temp).and_then(|mut file| {
+        file.write_all(content)?;
+        file.sync_all()
+    });
+    match result.and_then(|()| std::fs::rename(&temp, path))
+    {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp);
+            Err(e)
+        },
+    }
+}
+
+/// Who to credit in the footer, if the guide has summaries and the footer is wanted.
+pub fn footer<'a>(guide: &'a StudyGuide, options: &WriteOptions) -> Option<&'a Attribution>
+{
+    guide.attribution.as_ref().filter(|_| options.footer)
+}
+
+/// Title used for the top of the document.
+pub fn title(guide: &StudyGuide) -> &str
+{
+    guide.title.as_deref().filter(|title| !title.is_empty()).unwrap_or("Study Guide")
+}
+
+/// A heading of the document below its title, in the order they are written.
+pub struct Heading<'a>
+{
+    pub text: &'a str,
+    /// 1 for the top level.
+    pub level: usize,
+    /// Unique within the document.
+    pub anchor: String,
+    /// The topic under the heading, `None` for a section header.
+    pub entry: Option<&'a TopicEntry>,
+}
+
+/// Section headers and topics as headings, with topics one level under the section they are in
+/// and sub-topics one level under their parent.
+pub fn outline(guide: &StudyGuide) -> Vec<Heading<'_>>
+{
+    let mut headings = Vec::<Heading>::with_capacity(guide.entries.len());
+    let mut anchors = Vec::<String>::with_capacity(guide.entries.len());
+    let mut under_section = false;
+    for (i, entry) in guide.entries.iter().enumerate()
+    {
+        if guide.starts_section(i)
+        {
+            if let Some(title) = guide.section_title(entry)
+            {
+                under_section = true;
+                headings.push(Heading {
+                    text: title,
+                    level: 1,
+                    anchor: anchor(title, &mut anchors),
+                    entry: None,
+                });
+            }
+        }
+        headings.push(Heading {
+            text: &entry.query,
+            level: 1 + under_section as usize + entry.depth,
+            anchor: anchor(&entry.query, &mut anchors),
+            entry: Some(entry),
+        });
+    }
+    headings
+}
+
+/// GitHub style anchor: lowercase, spaces to hyphens, punctuation dropped and `-1`, `-2`, ...
+/// appended to repeats.
+fn anchor(text: &str, anchors: &mut Vec<String>) -> String
+{
+    let base = text.to_lowercase()
+        .chars()
+        .filter_map(|c| match c
+        {
+            ' ' | '-' => Some('-'),
+            c if c.is_alphanumeric() || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect::<String>();
+    let mut anchor = base.clone();
+    let mut n = 0;
+    while anchors.contains(&anchor)
+    {
+        n += 1;
+        anchor = format!("{}-{}", base, n);
+    }
+    anchors.push(anchor.clone());
+    anchor
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn outline_levels_and_anchors()
+    {
+        let guide = parser::parse("Cells:\n    Mitosis!\n        Prophase\n    Mitosis\n");
+        let outline = outline(&guide);
+        let headings = outline.iter().map(|heading| (heading.text, heading.level, heading.anchor.as_str())).collect::<Vec<_>>();
+        assert_eq!(headings, [("Cells", 1, "cells"), ("Mitosis!", 2, "mitosis"), ("Prophase", 3, "prophase"), ("Mitosis", 2, "mitosis-1")]);
+        assert!(outline[0].entry.is_none());
+    }
+
+    #[test]
+    fn formats_by_name()
+    {
+        assert_eq!(Format::from_name("MD"), Some(Format::Markdown));
+        assert_eq!(Format::from_extension("markdown"), Some(Format::Markdown));
+        assert_eq!(Format::from_extension("TXT"), Some(Format::Text));
+        assert_eq!(Format::from_name("Word"), Some(Format::Docx));
+        assert_eq!(Format::from_extension("HTM"), Some(Format::Html));
+        assert_eq!(Format::from_extension("apkg"), Some(Format::Anki));
+        assert_eq!(Format::from_extension("exe"), None);
+        assert_eq!(title(&StudyGuide::default()), "Study Guide");
+    }
+
+    #[test]
+    fn write_file_replaces_the_file()
+    {
+        let path = std::env::temp_dir().join(format!("study-guide-filler-{}.txt", std::process::id()));
+        std::fs::write(&path, "old").unwrap();
+        write_file(&path, b"new").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,754 @@
+use std::io::{self, Write};
+use crate::guide::StudyGuide;
+use crate::writer::{self, Heading, PageLayout, WriteOptions};
+
+// US Letter in points.
+const PAGE_WIDTH: f32 = 612.0;
+const PAGE_HEIGHT: f32 = 792.0;
+const POINTS_PER_MM: f32 = 72.0 / 25.4;
+const COLUMN_GAP: f32 = 18.0;
+const LINE_HEIGHT: f32 = 1.35;
+const MIN_CONTENT_WIDTH: f32 = 144.0;
+/// Lowest the page numbers go, so they stay on the page with no margin.
+const MIN_NUMBER_Y: f32 = 18.0;
+/// Characters named in the warning about those the fonts can't show.
+const MAX_LISTED: usize = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Font
+{
+    Regular,
+    Bold,
+    Oblique,
+}
+
+impl Font
+{
+    const ALL: [Font; 3] = [Font::Regular, Font::Bold, Font::Oblique];
+
+    fn resource(&self) -> &'static str
+    {
+        match self
+        {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+            Font::Oblique => "F3",
+        }
+    }
+
+    fn base_font(&self) -> &'static str
+    {
+        match self
+        {
+            Font::Regular => "Helvetica",
+            Font::Bold => "Helvetica-Bold",
+            Font::Oblique => "Helvetica-Oblique",
+        }
+    }
+
+    fn width(&self, text: &[u8], size: f32) -> f32
+    {
+        text.iter().map(|byte| glyph_width(*self == Font::Bold, *byte) as f32).sum::<f32>() * size / 1000.0
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Link
+{
+    Uri(String),
+    /// Page index and the height on it to jump to.
+    Page(usize, f32),
+}
+
+struct Text
+{
+    font: Font,
+    size: f32,
+    x: f32,
+    y: f32,
+    text: Vec<u8>,
+    gray: f32,
+    link: Option<Link>,
+}
+
+#[derive(Default)]
+struct Page
+{
+    texts: Vec<Text>,
+}
+
+/// Flows lines top to bottom through the columns of as many pages as it needs.
+struct Layout
+{
+    margin: f32,
+    pages: Vec<Page>,
+    columns: usize,
+    column: usize,
+    y: f32,
+}
+
+impl Layout
+{
+    fn new(layout: &PageLayout, columns: usize) -> Layout
+    {
+        let margin = (layout.margin.max(0.0) * POINTS_PER_MM).min((PAGE_WIDTH - MIN_CONTENT_WIDTH) / 2.0);
+        Layout {
+            margin,
+            pages: Vec::new(),
+            columns: columns.max(1),
+            column: 0,
+            y: 0.0,
+        }
+    }
+
+    fn top(&self) -> f32
+    {
+        PAGE_HEIGHT - self.margin
+    }
+
+    /// Above the page number, however small the margin.
+    fn bottom(&self) -> f32
+    {
+        self.margin.max(2.0 * MIN_NUMBER_Y)
+    }
+
+    fn column_width(&self) -> f32
+    {
+        (PAGE_WIDTH - 2.0 * self.margin - COLUMN_GAP * (self.columns - 1) as f32) / self.columns as f32
+    }
+
+    fn column_x(&self) -> f32
+    {
+        self.margin + self.column as f32 * (self.column_width() + COLUMN_GAP)
+    }
+
+    fn new_page(&mut self)
+    {
+        self.pages.push(Page::default());
+        self.column = 0;
+        self.y = self.top();
+    }
+
+    fn next_column(&mut self)
+    {
+        if self.column + 1 < self.columns
+        {
+            self.column += 1;
+            self.y = self.top();
+        }
+        else
+        {
+            self.new_page();
+        }
+    }
+
+    /// Moves on to the next column unless `height` still fits in this one.
+    fn ensure(&mut self, height: f32)
+    {
+        if self.pages.is_empty()
+        {
+            self.new_page();
+        }
+        else if self.y - height < self.bottom() && self.y < self.top()
+        {
+            self.next_column();
+        }
+    }
+
+    fn space(&mut self, height: f32)
+    {
+        if !self.pages.is_empty() && self.y < self.top()
+        {
+            self.y -= height;
+        }
+    }
+
+    fn line(&mut self, font: Font, size: f32, text: Vec<u8>, x: f32, gray: f32, link: Option<Link>)
+    {
+        self.ensure(size * LINE_HEIGHT);
+        let text = Text {
+            font,
+            size,
+            x: self.column_x() + x,
+            y: self.y - size,
+            text,
+            gray,
+            link,
+        };
+        self.pages.last_mut().unwrap().texts.push(text);
+        self.y -= size * LINE_HEIGHT;
+    }
+
+    fn paragraph(&mut self, font: Font, size: f32, text: &str, gray: f32, link: Option<Link>)
+    {
+        for line in wrap(font, size, &encode(text), self.column_width())
+        {
+            self.line(font, size, line, 0.0, gray, link.clone());
+        }
+    }
+
+    /// Returns the page index and height the heading starts at.
+    fn heading(&mut self, size: f32, text: &str, body_size: f32) -> (usize, f32)
+    {
+        let lines = wrap(Font::Bold, size, &encode(text), self.column_width());
+        // Keep the heading together with the start of its text.
+        self.ensure(lines.len() as f32 * size * LINE_HEIGHT + 2.0 * body_size * LINE_HEIGHT);
+        let position = (self.pages.len() - 1, self.y);
+        for line in lines
+        {
+            self.line(Font::Bold, size, line, 0.0, 0.0, None);
+        }
+        position
+    }
+}
+
+pub fn write<W: Write>(guide: &StudyGuide, options: &WriteOptions, out: &mut W) -> io::Result<()>
+{
+    let layout = &options.layout;
+    let size = layout.font_size.clamp(6.0, 24.0);
+    let title = writer::title(guide);
+
+    let mut body = Layout::new(layout, layout.columns);
+    let outline = writer::outline(guide);
+    let mut headings = Vec::with_capacity(outline.len());
+    if !layout.title_page
+    {
+        body.paragraph(Font::Bold, size * 2.0, title, 0.0, None);
+        body.space(size);
+    }
+    for heading in &outline
+    {
+        body.space(size * 0.8);
+        headings.push(body.heading(heading_size(heading, size), heading.text, size));
+        let Some(entry) = heading.entry.filter(|entry| entry.enabled) else {
+            continue;
+        };
+        if let Some(notes) = &entry.notes
+        {
+            body.paragraph(Font::Oblique, size, &format!("Notes: {}", notes), 0.0, None);
+            body.space(size * 0.3);
+        }
+        if let (Some(title), Some(url)) = (&entry.chosen_title, &entry.url)
+        {
+            body.paragraph(Font::Oblique, size * 0.85, &format!("Article: {}", title), 0.35, Some(Link::Uri(url.clone())));
+            body.space(size * 0.3);
+        }
+        match &entry.summary
+        {
+            Some(summary) => {
+                for paragraph in summary.lines().map(|line| line.trim()).filter(|line| !line.is_empty())
+                {
+                    body.paragraph(Font::Regular, size, paragraph, 0.0, None);
+                    body.space(size * 0.5);
+                }
+            },
+            None => body.paragraph(Font::Oblique, size, &entry.status.to_string(), 0.35, None),
+        }
+    }
+    if let Some(attribution) = writer::footer(guide, options)
+    {
+        body.space(size * 1.5);
+        body.paragraph(
+            Font::Oblique,
+            size * 0.8,
+            &format!("Summaries from {}, available under {}.", attribution.name, attribution.license),
+            0.35,
+            Some(Link::Uri(attribution.license_url.clone())),
+        );
+    }
+    if body.pages.is_empty()
+    {
+        body.new_page();
+    }
+
+    let mut pages = Vec::new();
+    if layout.title_page
+    {
+        pages.push(title_page(guide, title, size));
+    }
+    if options.table_of_contents
+    {
+        // The contents never change length, so lay them out once to count their pages.
+        let offset = pages.len() + contents(&outline, layout, size, &headings, 0).len();
+        pages.extend(contents(&outline, layout, size, &headings, offset));
+    }
+    pages.extend(body.pages);
+
+    let first = if layout.title_page { 1 } else { 0 };
+    let number_y = (body.margin / 2.0).max(MIN_NUMBER_Y);
+    for (i, page) in pages.iter_mut().enumerate().skip(first)
+    {
+        let number = encode(&(i + 1).to_string());
+        page.texts.push(Text {
+            font: Font::Regular,
+            size: size * 0.8,
+            x: (PAGE_WIDTH - Font::Regular.width(&number, size * 0.8)) / 2.0,
+            y: number_y,
+            text: number,
+            gray: 0.35,
+            link: None,
+        });
+    }
+
+    out.write_all(&serialize(&pages, title))?;
+    out.flush()
+}
+
+fn title_page(guide: &StudyGuide, title: &str, size: f32) -> Page
+{
+    let mut page = Page::default();
+    let mut y = PAGE_HEIGHT * 0.62;
+    let title_size = size * 2.6;
+    for line in wrap(Font::Bold, title_size, &encode(title), PAGE_WIDTH * 0.75)
+    {
+        page.texts.push(Text {
+            font: Font::Bold,
+            size: title_size,
+            x: (PAGE_WIDTH - Font::Bold.width(&line, title_size)) / 2.0,
+            y,
+            text: line,
+            gray: 0.0,
+            link: None,
+        });
+        y -= title_size * LINE_HEIGHT;
+    }
+
+    let topics = guide.entries.iter().filter(|entry| entry.enabled).count();
+    let mut lines = vec![format!("{} topic{}", topics, if topics == 1 { "" } else { "s" })];
+    lines.extend(guide.attribution.as_ref().map(|attribution| format!("Summaries from {}", attribution.name)));
+    y -= size;
+    for line in lines
+    {
+        let line = encode(&line);
+        page.texts.push(Text {
+            font: Font::Regular,
+            size: size * 1.2,
+            x: (PAGE_WIDTH - Font::Regular.width(&line, size * 1.2)) / 2.0,
+            y,
+            text: line,
+            gray: 0.35,
+            link: None,
+        });
+        y -= size * 1.2 * LINE_HEIGHT;
+    }
+    page
+}
+
+/// Lays out the contents with page numbers shifted by `offset`, the number of pages in front of the body.
+fn contents(outline: &[Heading], layout: &PageLayout, size: f32, headings: &[(usize, f32)], offset: usize) -> Vec<Page>
+{
+    let mut contents = Layout::new(layout, 1);
+    contents.paragraph(Font::Bold, size * 1.6, "Contents", 0.0, None);
+    contents.space(size * 0.8);
+
+    let width = contents.column_width();
+    let ellipsis = encode("...");
+    for (heading, (page, y)) in outline.iter().zip(headings)
+    {
+        let font = if heading.entry.is_none() { Font::Bold } else { Font::Regular };
+        let indent = (heading.level - 1) as f32 * size * 1.5;
+        let number = encode(&(page + offset + 1).to_string());
+        let number_width = Font::Regular.width(&number, size);
+        let room = width - indent - number_width - size;
+
+        let mut text = encode(heading.text);
+        if font.width(&text, size) > room
+        {
+            while !text.is_empty() && font.width(&text, size) + font.width(&ellipsis, size) > room
+            {
+                text.pop();
+            }
+            text.extend_from_slice(&ellipsis);
+        }
+
+        let link = Some(Link::Page(page + offset, *y));
+        contents.line(font, size, text, indent, 0.0, link.clone());
+        // Put the number on the same line as the heading.
+        contents.y += size * LINE_HEIGHT;
+        contents.line(Font::Regular, size, number, width - number_width, 0.0, link);
+    }
+    contents.pages
+}
+
+fn heading_size(heading: &Heading, size: f32) -> f32
+{
+    match heading.level
+    {
+        1 => size * 1.4,
+        2 => size * 1.2,
+        _ => size * 1.05,
+    }
+}
+
+fn wrap(font: Font, size: f32, text: &[u8], width: f32) -> Vec<Vec<u8>>
+{
+    let space = font.width(b" ", size);
+    let mut lines = Vec::new();
+    let mut line = Vec::<u8>::new();
+    let mut line_width = 0.0;
+    for word in text.split(|byte| *byte == b' ').filter(|word| !word.is_empty())
+    {
+        let word_width = font.width(word, size);
+        if !line.is_empty() && line_width + space + word_width > width
+        {
+            lines.push(std::mem::take(&mut line));
+            line_width = 0.0;
+        }
+        if line.is_empty() && word_width > width
+        {
+            // Too long for any line, so break it wherever it runs out of room.
+            for byte in word
+            {
+                let byte_width = font.width(&[*byte], size);
+                if !line.is_empty() && line_width + byte_width > width
+                {
+                    lines.push(std::mem::take(&mut line));
+                    line_width = 0.0;
+                }
+                line.push(*byte);
+                line_width += byte_width;
+            }
+            continue;
+        }
+        if !line.is_empty()
+        {
+            line.push(b' ');
+            line_width += space;
+        }
+        line.extend_from_slice(word);
+        line_width += word_width;
+    }
+    if !line.is_empty()
+    {
+        lines.push(line);
+    }
+    lines
+}
+
+fn serialize(pages: &[Page], title: &str) -> Vec<u8>
+{
+    let mut objects = Vec::<Vec<u8>>::new();
+    let reserve = |objects: &mut Vec<Vec<u8>>| {
+        objects.push(Vec::new());
+        objects.len()
+    };
+
+    let catalog = reserve(&mut objects);
+    let page_tree = reserve(&mut objects);
+    let info = reserve(&mut objects);
+    let fonts = Font::ALL.map(|_| reserve(&mut objects));
+    let page_ids = pages.iter().map(|_| reserve(&mut objects)).collect::<Vec<_>>();
+
+    objects[catalog - 1] = format!("<< /Type /Catalog /Pages {} 0 R >>", page_tree).into_bytes();
+    let kids = page_ids.iter().map(|id| format!("{} 0 R", id)).collect::<Vec<_>>().join(" ");
+    objects[page_tree - 1] = format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids, pages.len()).into_bytes();
+
+    let mut info_dict = b"<< /Title ".to_vec();
+    info_dict.extend(string(&encode(title)));
+    info_dict.extend(b" /Producer (Study Guide Filler) >>");
+    objects[info - 1] = info_dict;
+
+    for (font, id) in Font::ALL.iter().zip(fonts)
+    {
+        objects[id - 1] = format!("<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>", font.base_font()).into_bytes();
+    }
+    let resources = Font::ALL.iter()
+        .zip(fonts)
+        .map(|(font, id)| format!("/{} {} 0 R", font.resource(), id))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    for (page, page_id) in pages.iter().zip(&page_ids)
+    {
+        let mut content = Vec::<u8>::new();
+        let mut annotations = Vec::new();
+        for text in &page.texts
+        {
+            content.extend(format!("BT {} g /{} {:.2} Tf 1 0 0 1 {:.2} {:.2} Tm ", text.gray, text.font.resource(), text.size, text.x, text.y).into_bytes());
+            content.extend(string(&text.text));
+            content.extend(b" Tj ET\n");
+
+            if let Some(link) = &text.link
+            {
+                let rect = format!(
+                    "[{:.2} {:.2} {:.2} {:.2}]",
+                    text.x, text.y - text.size * 0.25, text.x + text.font.width(&text.text, text.size), text.y + text.size * 0.9,
+                );
+                let mut annotation = format!("<< /Type /Annot /Subtype /Link /Rect {} /Border [0 0 0] ", rect).into_bytes();
+                match link
+                {
+                    Link::Uri(uri) => {
+                        annotation.extend(b"/A << /S /URI /URI ");
+                        annotation.extend(string(uri.as_bytes()));
+                        annotation.extend(b" >> >>");
+                    },
+                    Link::Page(page, y) => {
+                        annotation.extend(format!("/Dest [{} 0 R /XYZ null {:.2} null] >>", page_ids[*page], y).into_bytes());
+                    },
+                }
+                objects.push(annotation);
+                annotations.push(objects.len());
+            }
+        }
+
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend(b"\nendstream");
+        objects.push(stream);
+        let content_id = objects.len();
+
+        let annotations = annotations.iter().map(|id| format!("{} 0 R", id)).collect::<Vec<_>>().join(" ");
+        objects[page_id - 1] = format!(
+            "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources << /Font << {} >> >> /Contents {} 0 R /Annots [{}] >>",
+            page_tree, PAGE_WIDTH, PAGE_HEIGHT, resources, content_id, annotations,
+        ).into_bytes();
+    }
+
+    let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate()
+    {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", i + 1).into_bytes());
+        pdf.extend(object);
+        pdf.extend(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+    for offset in offsets
+    {
+        pdf.extend(format!("{:010} 00000 n \n", offset).into_bytes());
+    }
+    pdf.extend(format!(
+        "trailer\n<< /Size {} /Root {} 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1, catalog, info, xref,
+    ).into_bytes());
+    pdf
+}
+
+/// A PDF literal string.
+fn string(text: &[u8]) -> Vec<u8>
+{
+    let mut string = Vec::with_capacity(text.len() + 2);
+    string.push(b'(');
+    for byte in text
+    {
+        if matches!(byte, b'\\' | b'(' | b')')
+        {
+            string.push(b'\\');
+        }
+        string.push(*byte);
+    }
+    string.push(b')');
+    string
+}
+
+/// Converts text to WinAnsiEncoding, the only encoding the standard fonts have without embedding.
+/// Characters outside it come out as `?`, see [`warning`].
+fn encode(text: &str) -> Vec<u8>
+{
+    text.chars()
+        .filter_map(|c| match c
+        {
+            '\t' | '\n' | '\r' => Some(b' '),
+            c if c.is_control() => None,
+            c => Some(win_ansi(c).unwrap_or(b'?')),
+        })
+        .collect()
+}
+
+fn win_ansi(c: char) -> Option<u8>
+{
+    match c
+    {
+        ' '..='~' | '\u{a0}'..='\u{ff}' => Some(c as u8),
+        '€' => Some(0x80),
+        '‚' => Some(0x82),
+        'ƒ' => Some(0x83),
+        '„' => Some(0x84),
+        '…' => Some(0x85),
+        '†' => Some(0x86),
+        '‡' => Some(0x87),
+        'ˆ' => Some(0x88),
+        '‰' => Some(0x89),
+        'Š' => Some(0x8a),
+        '‹' => Some(0x8b),
+        'Œ' => Some(0x8c),
+        'Ž' => Some(0x8e),
+        '‘' => Some(0x91),
+        '’' => Some(0x92),
+        '“' => Some(0x93),
+        '”' => Some(0x94),
+        '•' => Some(0x95),
+        '–' | '‐' | '‑' | '−' => Some(0x96),
+        '—' => Some(0x97),
+        '˜' => Some(0x98),
+        '™' => Some(0x99),
+        'š' => Some(0x9a),
+        '›' => Some(0x9b),
+        'œ' => Some(0x9c),
+        'ž' => Some(0x9e),
+        'Ÿ' => Some(0x9f),
+        _ => None,
+    }
+}
+
+/// Says which characters of the guide the fonts can't show, such as Cyrillic or Japanese
+/// summaries, since they are written as `?`.
+pub fn warning(guide: &StudyGuide) -> Option<String>
+{
+    let mut texts = vec![writer::title(guide)];
+    texts.extend(guide.sections.iter().filter_map(|section| section.title.as_deref()));
+    for entry in guide.entries.iter().filter(|entry| entry.enabled)
+    {
+        texts.push(&entry.query);
+        texts.extend([&entry.notes, &entry.chosen_title, &entry.summary].into_iter().filter_map(|text| text.as_deref()));
+    }
+
+    let mut missing = Vec::new();
+    for c in texts.into_iter().flat_map(str::chars)
+    {
+        if !c.is_control() && win_ansi(c).is_none() && !missing.contains(&c)
+        {
+            missing.push(c);
+        }
+    }
+    if missing.is_empty()
+    {
+        return None;
+    }
+    let mut listed = missing.iter().take(MAX_LISTED).map(char::to_string).collect::<Vec<_>>().join(" ");
+    if missing.len() > MAX_LISTED
+    {
+        listed.push_str(" ...");
+    }
+    Some(format!(
+        "The PDF fonts can't show {} of the characters in the guide ({}), they are written as \"?\". Export HTML or Word instead to keep them.",
+        missing.len(), listed,
+    ))
+}
+
+// Advance widths of the printable ASCII characters from the Adobe font metrics, in 1/1000 em.
+const HELVETICA: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+const HELVETICA_BOLD: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611,
+    975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556,
+    333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611,
+    611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+fn glyph_width(bold: bool, byte: u8) -> u16
+{
+    let table = if bold { &HELVETICA_BOLD } else { &HELVETICA };
+    match byte
+    {
+        b' '..=b'~' => table[(byte - b' ') as usize],
+        // Accented letters are as wide as the letter they are built on.
+        0xc0..=0xc5 => table[(b'A' - b' ') as usize],
+        0xc7 => table[(b'C' - b' ') as usize],
+        0xc8..=0xcb => table[(b'E' - b' ') as usize],
+        0xcc..=0xcf => table[(b'I' - b' ') as usize],
+        0xd1 => table[(b'N' - b' ') as usize],
+        0xd2..=0xd6 | 0xd8 => table[(b'O' - b' ') as usize],
+        0xd9..=0xdc => table[(b'U' - b' ') as usize],
+        0xdd | 0x9f => table[(b'Y' - b' ') as usize],
+        0xe0..=0xe5 => table[(b'a' - b' ') as usize],
+        0xe7 => table[(b'c' - b' ') as usize],
+        0xe8..=0xeb => table[(b'e' - b' ') as usize],
+        0xec..=0xef => table[(b'i' - b' ') as usize].max(278),
+        0xf1 => table[(b'n' - b' ') as usize],
+        0xf2..=0xf6 | 0xf8 => table[(b'o' - b' ') as usize],
+        0xf9..=0xfc => table[(b'u' - b' ') as usize],
+        0xfd | 0xff => table[(b'y' - b' ') as usize],
+        0x8a => table[(b'S' - b' ') as usize],
+        0x9a => table[(b's' - b' ') as usize],
+        0x8e => table[(b'Z' - b' ') as usize],
+        0x9e => table[(b'z' - b' ') as usize],
+        0x91 | 0x92 | 0x82 => if bold { 278 } else { 222 },
+        0x93 | 0x94 | 0x84 => if bold { 500 } else { 333 },
+        0x95 => 350,
+        0x97 | 0x85 | 0x89 => 1000,
+        0x8c | 0xc6 => 1000,
+        0x9c | 0xe6 => if bold { 944 } else { 889 },
+        0xa0 => 278,
+        0xa9 | 0xae => 737,
+        0xb0 => 400,
+        0xdf => 611,
+        _ => 556,
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::guide::TopicEntry;
+
+    fn guide(summary: &str) -> StudyGuide
+    {
+        let mut entry = TopicEntry::new("Pushkin");
+        entry.chosen_title = Some("Alexander Pushkin".to_string());
+        entry.summary = Some(summary.to_string());
+        StudyGuide { entries: vec![entry], ..StudyGuide::default() }
+    }
+
+    #[test]
+    fn encodes_win_ansi()
+    {
+        assert_eq!(encode("Café – “x”\tÿ"), b"Caf\xe9 \x96 \x93x\x94 \xff");
+        assert_eq!(encode("Пушкин 日本"), b"?????? ??");
+    }
+
+    #[test]
+    fn warns_about_lost_characters()
+    {
+        assert_eq!(warning(&guide("A Russian poet — “the sun of Russian poetry”.")), None);
+        let warning = warning(&guide("Алекса́ндр Пушкин, 日本")).unwrap();
+        assert!(warning.contains("16 of the characters"), "{}", warning);
+        assert!(warning.contains("(А л е к с а ́ н д р ...)"), "{}", warning);
+    }
+
+    #[test]
+    fn writes_pdf()
+    {
+        let mut out = Vec::new();
+        write(&guide("(Parentheses) and a \\ backslash."), &WriteOptions::default(), &mut out).unwrap();
+        let pdf = String::from_utf8_lossy(&out);
+        assert!(pdf.starts_with("%PDF-1.4"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        assert!(pdf.contains("(\\(Parentheses\\) and a \\\\ backslash.)"));
+        assert!(pdf.contains("/BaseFont /Helvetica-Bold"));
+    }
+
+    #[test]
+    fn page_numbers_stay_on_the_page()
+    {
+        let mut options = WriteOptions::default();
+        options.layout.margin = 0.0;
+        options.layout.title_page = false;
+        options.table_of_contents = false;
+        let mut out = Vec::new();
+        write(&guide("Summary."), &options, &mut out).unwrap();
+        let pdf = String::from_utf8_lossy(&out);
+        assert!(pdf.contains(&format!("{:.2} Tm (1) Tj", MIN_NUMBER_Y)), "{}", pdf);
+    }
+
+    #[test]
+    fn wraps_to_width()
+    {
+        let lines = wrap(Font::Regular, 10.0, b"aaa bbb ccc", Font::Regular.width(b"aaa bbb", 10.0));
+        assert_eq!(lines, [b"aaa bbb".to_vec(), b"ccc".to_vec()]);
+        let lines = wrap(Font::Regular, 10.0, b"abcdef", Font::Regular.width(b"abc", 10.0));
+        assert_eq!(lines, [b"abc".to_vec(), b"def".to_vec()]);
+    }
+}
@@ -0,0 +1,41 @@
+use std::io::{self, Write};
+use crate::guide::StudyGuide;
+
+pub fn write<W: Write>(guide: &StudyGuide, out: &mut W) -> io::Result<()>
+{
+    for (i, entry) in guide.entries.iter().enumerate()
+    {
+        if let Some(title) = guide.section_title(entry).filter(|_| guide.starts_section(i))
+        {
+            writeln!(out, "{}\n", title)?;
+        }
+        let summary = if entry.enabled { entry.summary.as_deref() } else { None };
+        writeln!(out, "{}{}", "    ".repeat(entry.depth), entry.query)?;
+        if let Some(notes) = entry.notes.as_deref().filter(|_| entry.enabled)
+        {
+            writeln!(out, "Notes: {}", notes)?;
+        }
+        writeln!(out, "{}\n", summary.unwrap_or_default())?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn sections_topics_and_notes()
+    {
+        let mut guide = parser::parse("Cells:\n    Mitosis\n        Prophase\n");
+        guide.entries[0].summary = Some("Cell division.".to_string());
+        guide.entries[0].notes = Some("Exam".to_string());
+        guide.entries[1].enabled = false;
+        guide.entries[1].summary = Some("Left out.".to_string());
+        let mut out = Vec::new();
+        write(&guide, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "Cells\n\nMitosis\nNotes: Exam\nCell division.\n\n    Prophase\n\n\n");
+    }
+}
@@ -0,0 +1,131 @@
+pub(crate) struct Tag<'a>
+{
+    /// The element name, or `#text` for the text between tags.
+    pub name: &'a str,
+    pub closing: bool,
+    /// Self-closing, like `<w:b/>`.
+    pub empty: bool,
+    pub text: &'a str,
+    /// Byte range of the whole tag or text in the XML.
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Tag<'_>
+{
+    pub fn attribute(&self, name: &str) -> Option<String>
+    {
+        let start = self.text.find(&format!(" {}=", name))? + name.len() + 2;
+        let quote = self.text[start..].chars().next()?;
+        let value = &self.text[start + 1..];
+        value.find(quote).map(|end| unescape(&value[..end]))
+    }
+}
+
+/// Splits XML into tags and the text between them. Enough for the parts Word writes and the HTML
+/// of a page extract, which have no DTDs, and whose CDATA and comments can be skipped.
+pub(crate) fn tags(xml: &str) -> Vec<Tag<'_>>
+{
+    let mut tags = Vec::new();
+    let mut rest = xml;
+    while let Some(open) = rest.find('<')
+    {
+        let offset = xml.len() - rest.len();
+        if open > 0
+        {
+            tags.push(Tag { name: "#text", closing: false, empty: false, text: &rest[..open], start: offset, end: offset + open });
+        }
+        let Some(close) = rest[open..].find('>') else { break };
+        let text = &rest[open + 1..open + close];
+        let closing = text.starts_with('/');
+        let name = text.trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        tags.push(Tag { name, closing, empty: text.ends_with('/'), text, start: offset + open, end: offset + open + close + 1 });
+        rest = &rest[open + close + 1..];
+    }
+    if !rest.is_empty() && !rest.contains('<')
+    {
+        let offset = xml.len() - rest.len();
+        tags.push(Tag { name: "#text", closing: false, empty: false, text: rest, start: offset, end: xml.len() });
+    }
+    tags
+}
+
+pub(crate) fn unescape(text: &str) -> String
+{
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&')
+    {
+        unescaped.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(end) = rest.find(';') else { break };
+        let entity = &rest[1..end];
+        let c = match entity
+        {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity.strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16).ok())
+                .unwrap_or_else(|| entity.strip_prefix('#').and_then(|decimal| decimal.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match c
+        {
+            Some(c) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            },
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            },
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn splits_tags_and_text()
+    {
+        let xml = "<w:p><w:r><w:t xml:space=\"preserve\">A &amp; B</w:t><w:br/></w:r></w:p>";
+        let tags = tags(xml);
+        let names = tags.iter().map(|tag| (tag.name, tag.closing, tag.empty)).collect::<Vec<_>>();
+        assert_eq!(names, [
+            ("w:p", false, false),
+            ("w:r", false, false),
+            ("w:t", false, false),
+            ("#text", false, false),
+            ("w:t", true, false),
+            ("w:br", false, true),
+            ("w:r", true, false),
+            ("w:p", true, false),
+        ]);
+        assert_eq!(tags[2].attribute("xml:space").as_deref(), Some("preserve"));
+        assert_eq!(&xml[tags[3].start..tags[3].end], "A &amp; B");
+    }
+
+    #[test]
+    fn trailing_text()
+    {
+        let tags = tags("<b>bold</b> after");
+        assert_eq!(tags.last().map(|tag| tag.text), Some(" after"));
+    }
+
+    #[test]
+    fn entities()
+    {
+        assert_eq!(unescape("&lt;a&gt; &quot;b&apos; &#233;&#x4E2D; &bogus; & x"), "<a> \"b' é中 &bogus; & x");
+    }
+}